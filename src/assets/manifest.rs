@@ -0,0 +1,34 @@
+/*!
+List of every asset the game loads, keyed by the IDs the code refers to them with.
+*/
+use fxhash::FxHashMap;
+use serde::Deserialize;
+
+use crate::util::config::{self, ConfigError};
+
+pub const MANIFEST_PATH: &str = "config/assets.ron";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Manifest {
+    /// texture files by ID
+    pub textures: FxHashMap<String, String>,
+    /// files of animation definitions, keyed by animation name inside
+    pub animations: Vec<String>,
+    pub maps: FxHashMap<String, MapDef>,
+    /// map the game starts in
+    pub start_map: String,
+}
+
+/// Single level of an LDtk project
+#[derive(Debug, Deserialize)]
+pub struct MapDef {
+    pub path: String,
+    /// uid of the level inside of the project
+    pub level: i64,
+    /// int grid layer solid tiles are read from
+    pub layer: String,
+}
+
+pub fn load() -> Result<Manifest, ConfigError> {
+    config::load(MANIFEST_PATH)
+}
@@ -0,0 +1,258 @@
+/*!
+Assets listed in the manifest, preloaded behind a loading screen and referred to by ID.
+
+Nothing that fails to load stops the game: missing textures are drawn as a checkerboard,
+missing animations are logged when played, and startup reports every failure at once.
+*/
+pub mod manifest;
+
+use std::fmt;
+
+use fxhash::FxHashMap;
+use ldtk_rust::Level;
+use macroquad::file::{load_string, FileError};
+use macroquad::math::Rect;
+use macroquad::texture::{load_texture, FilterMode, Texture2D};
+use macroquad::window::next_frame;
+
+use self::manifest::{Manifest, MapDef};
+use crate::gfx::animation::parse_animations;
+use crate::gfx::AnimationStorage;
+use crate::map::tilemap::{parse_level, TilemapError};
+use crate::util::config::ConfigError;
+
+/// IDs are hashes of the names the manifest gives assets, so they can be made without a lookup,
+/// even as constants
+macro_rules! asset_id {
+    ($name:ident) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name(u64);
+
+        impl $name {
+            pub const fn new(name: &str) -> Self {
+                Self(hash(name.as_bytes()))
+            }
+        }
+    };
+}
+
+/// Constants for the assets the code refers to by name, listed in `KNOWN` to be checked against
+/// the manifest
+macro_rules! known_ids {
+    ($name:ident { $($constant:ident = $asset:literal),* $(,)? }) => {
+        impl $name {
+            $(pub const $constant: $name = $name::new($asset);)*
+            pub const KNOWN: &'static [&'static str] = &[$($asset),*];
+        }
+    };
+}
+
+/// FNV-1a, since it can be computed at compile time
+const fn hash(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+asset_id!(TextureId);
+asset_id!(MapId);
+
+known_ids!(TextureId {
+    SLIMEU = "slimeu",
+    GOBLIN_BASE = "goblin_base",
+});
+
+impl TextureId {
+    /// Never loaded, so it's always drawn as the placeholder
+    pub const PLACEHOLDER: TextureId = TextureId(0);
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    Manifest(ConfigError),
+    Texture(FileError),
+    /// file of animations or a map couldn't be read
+    File(FileError),
+    Animations(ConfigError),
+    Map(TilemapError),
+    /// the start map isn't listed in the manifest
+    UnknownMap(String),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Manifest(err) => write!(f, "manifest: {}", err),
+            AssetError::Texture(err) => write!(f, "texture: {}", err),
+            AssetError::File(err) => write!(f, "file: {}", err),
+            AssetError::Animations(err) => write!(f, "animations: {}", err),
+            AssetError::Map(err) => write!(f, "map: {}", err),
+            AssetError::UnknownMap(name) => write!(f, "map: {} isn't in the manifest", name),
+        }
+    }
+}
+
+/// Logs all the failures in a single message
+pub fn report(errors: &[AssetError]) {
+    if errors.is_empty() {
+        return;
+    }
+    let lines: Vec<_> = errors.iter().map(|err| format!("  {}", err)).collect();
+    log::error!("{} assets failed to load:\n{}", errors.len(), lines.join("\n"));
+}
+
+pub struct Assets {
+    pub manifest: Manifest,
+    textures: FxHashMap<TextureId, Texture2D>,
+    /// drawn in place of missing textures
+    placeholder: Texture2D,
+    maps: FxHashMap<MapId, Level>,
+}
+
+impl Assets {
+    pub fn new() -> Self {
+        Self {
+            manifest: Manifest::default(),
+            textures: FxHashMap::default(),
+            placeholder: checkerboard(),
+            maps: FxHashMap::default(),
+        }
+    }
+
+    /// Loads the manifest, then every asset in it asynchronously, drawing the progress between them.
+    /// Animations go into the storage, whatever fails to load is returned.
+    /// Textures the code has constants for but the manifest lacks are only warned about.
+    pub async fn preload(&mut self, animations: &mut AnimationStorage) -> Vec<AssetError> {
+        let mut errors = vec![];
+        match manifest::load() {
+            Ok(manifest) => {
+                if !manifest.maps.contains_key(&manifest.start_map) {
+                    errors.push(AssetError::UnknownMap(manifest.start_map.clone()));
+                }
+                self.manifest = manifest;
+            }
+            Err(err) => errors.push(AssetError::Manifest(err)),
+        }
+
+        let manifest = &self.manifest;
+        for name in TextureId::KNOWN.iter().filter(|name| !manifest.textures.contains_key(**name)) {
+            log::warn!("Texture {} is used by the game but missing from the manifest", name);
+        }
+
+        let total = manifest.textures.len() + manifest.animations.len() + manifest.maps.len();
+        let mut done = 0;
+
+        for (name, path) in manifest.textures.iter() {
+            show_progress(done, total).await;
+            match load_texture(path).await {
+                Ok(texture) => {
+                    texture.set_filter(FilterMode::Nearest);
+                    self.textures.insert(TextureId::new(name), texture);
+                }
+                Err(err) => errors.push(AssetError::Texture(err)),
+            }
+            done += 1;
+        }
+        for path in manifest.animations.iter() {
+            show_progress(done, total).await;
+            match load_string(path).await {
+                Ok(text) => match parse_animations(path, &text) {
+                    Ok(loaded) => animations.extend(loaded),
+                    Err(err) => errors.push(AssetError::Animations(err)),
+                },
+                Err(err) => errors.push(AssetError::File(err)),
+            }
+            done += 1;
+        }
+        for (name, def) in manifest.maps.iter() {
+            show_progress(done, total).await;
+            match load_string(&def.path).await {
+                Ok(text) => match parse_level(def, &text) {
+                    Ok(level) => {
+                        self.maps.insert(MapId::new(name), level);
+                    }
+                    Err(err) => errors.push(AssetError::Map(err)),
+                },
+                Err(err) => errors.push(AssetError::File(err)),
+            }
+            done += 1;
+        }
+        show_progress(done, total).await;
+
+        errors
+    }
+
+    pub fn texture(&self, id: TextureId) -> Option<Texture2D> {
+        self.textures.get(&id).copied()
+    }
+
+    /// Texture along with the area of it to draw, the whole placeholder if it's missing
+    pub fn texture_region(&self, id: TextureId, rect: Rect) -> (Texture2D, Option<Rect>) {
+        match self.texture(id) {
+            Some(texture) => (texture, Some(rect)),
+            None => (self.placeholder, None),
+        }
+    }
+
+    /// Level the game starts in along with its definition, if it loaded
+    pub fn start_map(&self) -> Option<(&MapDef, &Level)> {
+        let def = self.manifest.maps.get(&self.manifest.start_map)?;
+        let level = self.maps.get(&MapId::new(&self.manifest.start_map))?;
+        Some((def, level))
+    }
+
+    /// Texture loaded from the file
+    #[cfg(feature = "devtools")]
+    pub fn texture_by_path(&self, path: &std::path::Path) -> Option<TextureId> {
+        self.manifest
+            .textures
+            .iter()
+            .find(|(_, texture_path)| std::path::Path::new(texture_path) == path)
+            .map(|(name, _)| TextureId::new(name))
+    }
+
+    /// Returns the replaced texture
+    #[cfg(feature = "devtools")]
+    pub fn replace_texture(&mut self, id: TextureId, texture: Texture2D) -> Option<Texture2D> {
+        self.textures.insert(id, texture)
+    }
+
+    /// Reads the start map from its file again, the loaded one is kept if that fails
+    #[cfg(feature = "devtools")]
+    pub fn reload_start_map(&mut self) -> Result<(), AssetError> {
+        let name = &self.manifest.start_map;
+        let def =
+            self.manifest.maps.get(name).ok_or_else(|| AssetError::UnknownMap(name.clone()))?;
+        let level = crate::map::tilemap::load_level(def).map_err(AssetError::Map)?;
+        self.maps.insert(MapId::new(name), level);
+        Ok(())
+    }
+}
+
+async fn show_progress(done: usize, total: usize) {
+    crate::gfx::draw_loading_screen(done as f32 / total.max(1) as f32);
+    next_frame().await;
+}
+
+/// Magenta and black, hard to miss
+fn checkerboard() -> Texture2D {
+    const SIZE: u16 = 16;
+    const CELL: u16 = 4;
+
+    let mut bytes = Vec::with_capacity(SIZE as usize * SIZE as usize * 4);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let color =
+                if (x / CELL + y / CELL) % 2 == 0 { [255, 0, 255, 255] } else { [0, 0, 0, 255] };
+            bytes.extend_from_slice(&color);
+        }
+    }
+    let texture = Texture2D::from_rgba8(SIZE, SIZE, &bytes);
+    texture.set_filter(FilterMode::Nearest);
+    texture
+}
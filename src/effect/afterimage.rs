@@ -0,0 +1,58 @@
+use super::{tint::ColorTween, EffectData};
+use crate::gfx::Sprite;
+use crate::phx::Position;
+use crate::util::Easing;
+use crate::FRAMETIME;
+use hecs::{CommandBuffer, World};
+use macroquad::color::Color;
+
+/// Component, while active leaves behind fading copies of the owner's `Sprite`
+pub struct AfterimageTrail {
+    pub active: bool,
+    /// time between spawned afterimages
+    pub interval: f32,
+    /// how long a single afterimage takes to fade out
+    pub fade_duration: f32,
+    pub color: Color,
+    /// accumulated time
+    acc: f32,
+}
+
+impl AfterimageTrail {
+    pub fn new(interval: f32, fade_duration: f32, color: Color) -> Self {
+        Self { active: false, interval, fade_duration, color, acc: 0. }
+    }
+}
+
+/// Afterimages are effects bound to themselves, so they get despawned once faded out.
+pub fn afterimage_system(world: &mut World, cmd: &mut CommandBuffer) {
+    for (_eid, (trail, position, sprite)) in
+        world.query::<(&mut AfterimageTrail, &Position, &Sprite)>().iter()
+    {
+        if !trail.active {
+            trail.acc = trail.interval;
+            continue;
+        }
+
+        trail.acc += FRAMETIME;
+        if trail.acc < trail.interval {
+            continue;
+        }
+        trail.acc -= trail.interval;
+
+        let afterimage = world.reserve_entity();
+        let mut copy = sprite.clone();
+        copy.color = trail.color;
+        let transparent = Color::new(1., 1., 1., 0.);
+
+        cmd.insert(
+            afterimage,
+            (
+                *position,
+                copy,
+                EffectData::new(afterimage, trail.fade_duration),
+                ColorTween::new(Color::new(1., 1., 1., 1.), transparent, Easing::QuadOut),
+            ),
+        );
+    }
+}
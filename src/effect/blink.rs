@@ -0,0 +1,29 @@
+use super::{EffectData, Looks};
+use hecs::World;
+
+/// Toggles visibility of the parent, starting visible
+pub struct Blink {
+    /// time between each toggle
+    pub interval: f32,
+}
+
+impl Blink {
+    pub fn new(interval: f32) -> Self {
+        Self { interval }
+    }
+    fn visible(&self, elapsed: f32) -> bool {
+        if self.interval <= 0. {
+            return true;
+        }
+        (elapsed / self.interval) as u32 % 2 == 0
+    }
+}
+
+pub fn blink_system(world: &World, looks: &mut Looks) {
+    for (_eid, (blink, effect)) in world.query::<(&Blink, &EffectData)>().iter() {
+        if effect.is_active() {
+            let look = looks.entry(effect.parent).or_default();
+            look.visible &= blink.visible(effect.elapsed);
+        }
+    }
+}
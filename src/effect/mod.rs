@@ -1,24 +1,160 @@
+pub mod afterimage;
+pub mod blink;
+pub mod scale;
+pub mod shake;
 pub mod tint;
 
+use fxhash::FxHashMap;
+use glam::Vec2;
 use hecs::{CommandBuffer, Entity, World};
+use macroquad::color::{Color, WHITE};
 
+use crate::gfx::Sprite;
 use crate::FRAMETIME;
 
+/// Every effect entity has this, the effect itself is a separate component on the same entity.
 pub struct EffectData {
     pub parent: hecs::Entity,
+    /// time left
     pub duration: f32,
+    /// time passed since creation
+    pub elapsed: f32,
+}
+
+impl EffectData {
+    pub fn new(parent: Entity, duration: f32) -> Self {
+        Self { parent, duration, elapsed: 0. }
+    }
+    pub fn is_active(&self) -> bool {
+        !self.duration.is_sign_negative()
+    }
+    /// How far along the effect is, in range 0..=1
+    pub fn progress(&self) -> f32 {
+        let total = self.elapsed + self.duration;
+        if total > 0. {
+            (self.elapsed / total).clamp(0., 1.)
+        } else {
+            1.
+        }
+    }
+}
+
+/// Accumulated influence of all effects bound to a single parent.
+/// Effects touching the same property stack:
+/// - colors are multiplied component-wise
+/// - scales are multiplied
+/// - offsets are added
+/// - the parent is hidden if any effect hides it
+pub struct Look {
+    pub color: Color,
+    pub scale: Vec2,
+    pub offset: Vec2,
+    pub visible: bool,
+}
+
+impl Default for Look {
+    fn default() -> Self {
+        Self { color: WHITE, scale: Vec2::ONE, offset: Vec2::ZERO, visible: true }
+    }
+}
+
+pub type Looks = FxHashMap<Entity, Look>;
+
+/// Parent's `Sprite` values from before any effect touched it, restored once no effect is left.
+struct Unaffected {
+    color: Color,
+    scale: Vec2,
+    offset: Vec2,
+    visible: bool,
+}
+
+impl Unaffected {
+    fn from_sprite(sprite: &Sprite) -> Self {
+        Self {
+            color: sprite.color,
+            scale: sprite.scale,
+            offset: sprite.offset,
+            visible: sprite.visible,
+        }
+    }
+    fn restore(&self, sprite: &mut Sprite) {
+        sprite.color = self.color;
+        sprite.scale = self.scale;
+        sprite.offset = self.offset;
+        sprite.visible = self.visible;
+    }
 }
 
 pub fn effect_update_system(world: &mut World, cmd: &mut CommandBuffer) {
-    for (eid, effect_data) in world.query_mut::<&mut EffectData>() {
+    let mut orphans = vec![];
+    for (eid, effect_data) in world.query::<&mut EffectData>().iter() {
+        if !world.contains(effect_data.parent) {
+            orphans.push(eid);
+            continue;
+        }
         effect_update(eid, cmd, effect_data);
     }
+    // effects of despawned parents have nothing to act on
+    for eid in orphans {
+        let _ = world.despawn(eid);
+    }
 }
 
 fn effect_update(entity: Entity, command_buffer: &mut CommandBuffer, effect_data: &mut EffectData) {
     effect_data.duration -= FRAMETIME;
+    effect_data.elapsed += FRAMETIME;
 
-    if effect_data.duration.is_sign_negative() {
+    if !effect_data.is_active() {
         command_buffer.despawn(entity);
     }
 }
+
+/// Recomputes the `Sprite` of every parent from its original values and currently active effects.
+pub fn apply_effects_system(world: &mut World) {
+    let mut looks = Looks::default();
+
+    tint::tint_system(world, &mut looks);
+    tint::color_tween_system(world, &mut looks);
+    blink::blink_system(world, &mut looks);
+    scale::scale_system(world, &mut looks);
+    shake::shake_system(world, &mut looks);
+
+    for (&parent, look) in looks.iter() {
+        if world.get::<Unaffected>(parent).is_err() {
+            let unaffected = match world.get::<Sprite>(parent) {
+                Ok(sprite) => Unaffected::from_sprite(&sprite),
+                Err(_) => continue,
+            };
+            let _ = world.insert_one(parent, unaffected);
+        }
+
+        if let Ok(mut query) = world.query_one::<(&mut Sprite, &Unaffected)>(parent) {
+            if let Some((sprite, unaffected)) = query.get() {
+                apply_look(sprite, unaffected, look);
+            }
+        }
+    }
+
+    // parents left without effects go back to how they were
+    let mut finished = vec![];
+    for (parent, (sprite, unaffected)) in world.query_mut::<(&mut Sprite, &Unaffected)>() {
+        if !looks.contains_key(&parent) {
+            unaffected.restore(sprite);
+            finished.push(parent);
+        }
+    }
+    for parent in finished {
+        let _ = world.remove_one::<Unaffected>(parent);
+    }
+}
+
+fn apply_look(sprite: &mut Sprite, unaffected: &Unaffected, look: &Look) {
+    sprite.color = multiply_colors(unaffected.color, look.color);
+    sprite.scale = unaffected.scale * look.scale;
+    sprite.offset = unaffected.offset + look.offset;
+    sprite.visible = unaffected.visible && look.visible;
+}
+
+pub fn multiply_colors(a: Color, b: Color) -> Color {
+    Color::new(a.r * b.r, a.g * b.g, a.b * b.b, a.a * b.a)
+}
@@ -0,0 +1,47 @@
+use super::{EffectData, Looks};
+use crate::phx::LandedEvent;
+use crate::util::events::{EventBus, EventReader};
+use crate::util::{lerp, Easing};
+use glam::Vec2;
+use hecs::{CommandBuffer, World};
+
+/// Scales the parent from one value to another over the duration, i.e. squash and stretch
+pub struct ScaleChange {
+    pub from: Vec2,
+    pub to: Vec2,
+    pub easing: Easing,
+}
+
+impl ScaleChange {
+    pub fn new(from: Vec2, to: Vec2, easing: Easing) -> Self {
+        Self { from, to, easing }
+    }
+    /// Squash or stretch that springs back to normal size
+    pub fn squash(amount: Vec2) -> Self {
+        Self::new(amount, Vec2::ONE, Easing::BackOut)
+    }
+    fn sample(&self, progress: f32) -> Vec2 {
+        let t = self.easing.apply(progress);
+        Vec2::new(lerp(self.from.x, self.to.x, t), lerp(self.from.y, self.to.y, t))
+    }
+}
+
+pub fn scale_system(world: &World, looks: &mut Looks) {
+    for (_eid, (scale, effect)) in world.query::<(&ScaleChange, &EffectData)>().iter() {
+        if effect.is_active() {
+            let look = looks.entry(effect.parent).or_default();
+            look.scale *= scale.sample(effect.progress());
+        }
+    }
+}
+
+/// Squashes entities on the frame they land
+pub fn landing_squash_system(
+    events: &EventBus,
+    reader: &mut EventReader<LandedEvent>,
+    cmd: &mut CommandBuffer,
+) {
+    for LandedEvent { entity } in events.read(reader) {
+        cmd.spawn((EffectData::new(*entity, 0.2), ScaleChange::squash(Vec2::new(1.25, 0.75))));
+    }
+}
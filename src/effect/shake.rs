@@ -0,0 +1,33 @@
+use super::{EffectData, Looks};
+use glam::Vec2;
+use hecs::World;
+use std::f32::consts::TAU;
+
+/// Offsets the drawn parent back and forth, fading out over the duration
+pub struct Shake {
+    /// maximum offset in pixels
+    pub amplitude: f32,
+    /// oscillations per second
+    pub frequency: f32,
+}
+
+impl Shake {
+    pub fn new(amplitude: f32, frequency: f32) -> Self {
+        Self { amplitude, frequency }
+    }
+    fn sample(&self, elapsed: f32, progress: f32) -> Vec2 {
+        let strength = self.amplitude * (1. - progress);
+        let phase = elapsed * self.frequency * TAU;
+        // slightly different frequency on each axis so the motion doesn't look like a line
+        Vec2::new(phase.sin(), (phase * 1.3).cos()) * strength
+    }
+}
+
+pub fn shake_system(world: &World, looks: &mut Looks) {
+    for (_eid, (shake, effect)) in world.query::<(&Shake, &EffectData)>().iter() {
+        if effect.is_active() {
+            let look = looks.entry(effect.parent).or_default();
+            look.offset += shake.sample(effect.elapsed, effect.progress());
+        }
+    }
+}
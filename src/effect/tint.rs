@@ -1,36 +1,49 @@
-use super::EffectData;
-use crate::gfx::Sprite;
+use super::{multiply_colors, EffectData, Looks};
+use crate::util::{lerp_color, Easing};
 use hecs::World;
-use macroquad::color::{Color, WHITE};
+use macroquad::color::Color;
 
+/// Tints the parent with a constant color for the whole duration
 pub struct TintChange {
-    /// Color set when effect begins
-    pub on_creation: Color,
-    /// Color set after effect ends
-    pub on_deletion: Color,
-    pub new: bool,
+    pub color: Color,
 }
 
 impl TintChange {
-    pub fn new(on_creation: Color) -> Self {
-        Self { on_creation, on_deletion: WHITE, new: true }
+    pub fn new(color: Color) -> Self {
+        Self { color }
     }
 }
 
-pub fn tint_system(world: &mut World) {
-    for (_eid, (tint, effect)) in world.query::<(&mut TintChange, &EffectData)>().iter() {
-        if tint.new {
-            // world.get::<&mut Sprite>(effect.parent).unwrap();
-            if let Ok(mut sprite) = world.get_mut::<Sprite>(effect.parent) {
-                sprite.color = tint.on_creation;
-            }
-            tint.new = false;
+/// Tints the parent with a color changing over the duration of the effect
+pub struct ColorTween {
+    pub from: Color,
+    pub to: Color,
+    pub easing: Easing,
+}
+
+impl ColorTween {
+    pub fn new(from: Color, to: Color, easing: Easing) -> Self {
+        Self { from, to, easing }
+    }
+    fn sample(&self, progress: f32) -> Color {
+        lerp_color(self.from, self.to, self.easing.apply(progress))
+    }
+}
+
+pub fn tint_system(world: &World, looks: &mut Looks) {
+    for (_eid, (tint, effect)) in world.query::<(&TintChange, &EffectData)>().iter() {
+        if effect.is_active() {
+            let look = looks.entry(effect.parent).or_default();
+            look.color = multiply_colors(look.color, tint.color);
         }
+    }
+}
 
-        if effect.duration.is_sign_negative() {
-            if let Ok(mut sprite) = world.get_mut::<Sprite>(effect.parent) {
-                sprite.color = tint.on_deletion;
-            }
+pub fn color_tween_system(world: &World, looks: &mut Looks) {
+    for (_eid, (tween, effect)) in world.query::<(&ColorTween, &EffectData)>().iter() {
+        if effect.is_active() {
+            let look = looks.entry(effect.parent).or_default();
+            look.color = multiply_colors(look.color, tween.sample(effect.progress()));
         }
     }
 }
@@ -1,22 +1,46 @@
 pub mod agent;
 mod ai;
 pub mod combat;
+#[cfg(feature = "devtools")]
+pub mod devtools;
+pub mod inbox;
 pub mod resources;
+pub mod schedule;
 pub mod stage;
 
-use hecs::{CommandBuffer, World};
+use hecs::World;
 
-use macroquad::texture::{load_texture, FilterMode, Texture2D};
-
-use crate::gfx::TextureStorage;
+use crate::assets::{AssetError, Assets, TextureId};
+use crate::map::tilemap::{Tilemap, TilemapError};
 
 use self::agent::controller::update_fsm_system;
 use self::resources::Resources;
+use self::schedule::{Schedule, ScheduleBuilder};
+use self::stage::Room;
 
 pub struct Game {
     pub world: World,
     pub resources: Resources,
-    pub textures: TextureStorage,
+    pub assets: Assets,
+    pub schedule: Schedule,
+    /// colliders created from the tilemap
+    map_colliders: Vec<resphys::ColliderHandle>,
+    #[cfg(feature = "devtools")]
+    pub profiler: crate::util::profiler::Profiler,
+    #[cfg(feature = "devtools")]
+    pub time_control: crate::util::time_control::TimeControl,
+    #[cfg(feature = "devtools")]
+    pub console: self::devtools::console::Console,
+    #[cfg(feature = "devtools")]
+    pub overlays: self::devtools::Overlays,
+    #[cfg(feature = "devtools")]
+    pub inspector: self::devtools::inspector::Inspector,
+    /// watches the files listed in the manifest, so it's set up once the assets are loaded
+    #[cfg(feature = "devtools")]
+    pub hot_reload: Option<self::devtools::hot_reload::HotReload>,
+    /// button bound to the next physical input once the console closes
+    #[cfg(feature = "devtools")]
+    pub rebinding: Option<crate::util::input::Button>,
 }
 
 impl Game {
@@ -24,56 +48,91 @@ impl Game {
         let world = World::new();
         let resources = Resources::new();
 
-        let textures = TextureStorage::default();
-
-        Self { world, resources, textures }
+        let assets = Assets::new();
+        let schedule = build_schedule();
+
+        Self {
+            world,
+            resources,
+            assets,
+            schedule,
+            map_colliders: vec![],
+            #[cfg(feature = "devtools")]
+            profiler: crate::util::profiler::Profiler::new(),
+            #[cfg(feature = "devtools")]
+            time_control: crate::util::time_control::TimeControl::new(),
+            #[cfg(feature = "devtools")]
+            console: self::devtools::console::Console::new(),
+            #[cfg(feature = "devtools")]
+            overlays: self::devtools::Overlays::new(),
+            #[cfg(feature = "devtools")]
+            inspector: self::devtools::inspector::Inspector::new(),
+            #[cfg(feature = "devtools")]
+            hot_reload: None,
+            #[cfg(feature = "devtools")]
+            rebinding: None,
+        }
     }
+    /// Preloads the assets and sets up the world, assets that fail to load are reported together
     pub async fn init(&mut self) {
-        use self::agent::controller::PlayerControlledV2;
-        use self::ai::{AiControlled, HitMemory};
+        use self::agent::controller::{DashAbility, HorizontalLock, PlayerControlledV2};
+        use self::agent::profile::ProfileKey;
         use self::combat::CombatStats;
+        use crate::effect::afterimage::AfterimageTrail;
         use crate::gfx::Sprite;
-        use crate::phx::{Gravity, Hitbox, OnGround, Position, Velocity};
+        use crate::particle::Emitter;
+        use crate::phx::{Gravity, Hitbox, OnGround, OnWall, Position, Velocity};
         use glam::Vec2;
 
-        let slimeu_texture: Texture2D = load_texture("media/slimeu.png").await.unwrap();
-        slimeu_texture.set_filter(FilterMode::Nearest);
-
-        let goblin_texture: Texture2D = load_texture("media/goblin_base.png").await.unwrap();
-        goblin_texture.set_filter(FilterMode::Nearest);
-
-        self.textures.insert("slimeu".into(), slimeu_texture);
-        self.textures.insert("goblin_base".into(), goblin_texture);
-
+        let mut errors = self.assets.preload(&mut self.resources.animations).await;
+        #[cfg(feature = "devtools")]
         {
-            use crate::gfx::{AnimationTemplate, Frame};
-            use macroquad::math::Rect;
-            let animation_storage = &mut self.resources.animations;
-            // TODO: Safer API that doesnt allow empty animation
-            let slimeu_static = AnimationTemplate {
-                rect: Rect::new(0., 0., 16., 16.),
-                move_by: 16.,
-                repeat: false,
-                texture_name: "slimeu".to_owned(),
-                frames: vec![Frame { duration: 0.1 }; 1],
-            };
-            animation_storage.insert("slimeu_static".into(), slimeu_static);
-            let slimeu_idle = AnimationTemplate {
-                rect: Rect::new(16., 0., 16., 16.),
-                move_by: 16.,
-                repeat: true,
-                texture_name: "slimeu".to_owned(),
-                frames: vec![Frame { duration: 0.1 }; 5],
-            };
-            animation_storage.insert("slimeu_idle".into(), slimeu_idle);
-            let slimeu_run = AnimationTemplate {
-                rect: Rect::new(0., 16., 16., 16.),
-                move_by: 16.,
-                repeat: true,
-                texture_name: "slimeu".to_owned(),
-                frames: vec![Frame { duration: 0.08 }; 8],
-            };
-            animation_storage.insert("slimeu_run".into(), slimeu_run);
+            let hot_reload = self::devtools::hot_reload::HotReload::new(&self.assets.manifest);
+            self.hot_reload = Some(hot_reload);
+        }
+        {
+            use crate::particle::ParticleTemplate;
+            use macroquad::color::Color;
+            let particles = &mut self.resources.particles;
+            particles.insert_template(
+                "dust",
+                ParticleTemplate {
+                    lifetime: 0.35,
+                    velocity: Vec2::new(0., -8.),
+                    velocity_spread: Vec2::new(10., 4.),
+                    gravity: Vec2::new(0., 16.),
+                    color_start: Color::new(0.85, 0.85, 0.8, 0.8),
+                    color_end: Color::new(0.85, 0.85, 0.8, 0.),
+                    texture: None,
+                    size: 1.,
+                },
+            );
+            particles.insert_template(
+                "landing_puff",
+                ParticleTemplate {
+                    lifetime: 0.3,
+                    velocity: Vec2::new(0., -6.),
+                    velocity_spread: Vec2::new(32., 4.),
+                    gravity: Vec2::new(0., 8.),
+                    color_start: Color::new(0.9, 0.9, 0.85, 0.9),
+                    color_end: Color::new(0.9, 0.9, 0.85, 0.),
+                    texture: None,
+                    size: 2.,
+                },
+            );
+            particles.insert_template(
+                "hit_spark",
+                ParticleTemplate {
+                    lifetime: 0.2,
+                    velocity: Vec2::new(0., -24.),
+                    velocity_spread: Vec2::new(64., 48.),
+                    gravity: Vec2::new(0., 128.),
+                    color_start: Color::new(1., 0.95, 0.6, 1.),
+                    color_end: Color::new(1., 0.3, 0., 0.),
+                    texture: None,
+                    size: 1.,
+                },
+            );
         }
 
         let (player_bhandle, player_chandle) =
@@ -83,7 +142,7 @@ impl Game {
 
         self.world.spawn((
             Position { src: Vec2::new(10.0, 10.0) },
-            Sprite::new("slimeu".to_owned(), 16., 0., 16., 16.),
+            Sprite::new(TextureId::SLIMEU, 16., 0., 16., 16.),
             crate::gfx::Animation::new(animation_storage, "slimeu_run"),
         ));
 
@@ -92,99 +151,259 @@ impl Game {
         let player_entity = self.world.spawn((
             Position { src: Vec2::new(100.0, 60.0) },
             Velocity { src: Vec2::new(0., 0.) },
-            Gravity::new(Vec2::new(0.0, 448. / 60.)),
+            Gravity::from_profile(self.resources.movement.get(ProfileKey::Player)),
+            ProfileKey::Player,
             OnGround::new(&mut self.resources, player_chandle),
+            OnWall::new(&mut self.resources, player_chandle),
+            HorizontalLock(0.),
+            DashAbility::new(),
+            AfterimageTrail::new(0.03, 0.2, macroquad::color_u8!(120, 200, 255, 160)),
             Hitbox::new(player_chandle),
             CombatStats::new(),
             PlayerControlledV2::new(),
+            Emitter::new("dust", 12., 4, Vec2::new(0., 8.)),
             player_sprite,
             player_animation,
         ));
 
-        let (enemy_bhandle, enemy_chandle) = makeshift_enemy_dynamic_collider(&mut self.resources);
+        #[cfg(feature = "devtools")]
+        self.world
+            .insert_one(player_entity, crate::util::transition_log::TransitionLog::new())
+            .unwrap();
+
+        // setup camera to just follow player immediately, for now
+        self.resources.camera.target = Some(player_entity);
+
+        self.resources.body_entity_map.insert(player_bhandle, player_entity);
+        self.spawn_enemy(Vec2::new(80.0, 40.0));
+        // a start map that failed to load is among the errors already
+        if let Err(err) = self.load_map() {
+            errors.push(AssetError::Map(err));
+        }
+
+        crate::assets::report(&errors);
+    }
+    pub fn spawn_enemy(&mut self, position: glam::Vec2) -> hecs::Entity {
+        use self::agent::profile::ProfileKey;
+        use self::ai::AiControlled;
+        use self::combat::CombatStats;
+        use self::inbox::Inbox;
+        use crate::gfx::Sprite;
+        use crate::phx::{Gravity, Hitbox, OnGround, Position, Velocity};
+        use glam::Vec2;
+
+        // placeholder is drawn at the size of the sprite
+        let texture = TextureId::GOBLIN_BASE;
+        let (width, height) = self
+            .assets
+            .texture(texture)
+            .map_or((16., 16.), |texture| (texture.width(), texture.height()));
+        let (enemy_bhandle, enemy_chandle) =
+            makeshift_enemy_dynamic_collider(&mut self.resources, position);
         let enemy_entity = self.world.spawn((
-            Position { src: Vec2::new(80.0, 40.0) },
-            Sprite::new(
-                "goblin_base".to_owned(),
-                0.,
-                0.,
-                goblin_texture.width(),
-                goblin_texture.height(),
-            ),
+            Position { src: position },
+            Sprite::new(texture, 0., 0., width, height),
             Velocity { src: Vec2::new(0., 0.) },
-            Gravity::new(Vec2::new(0.0, 448. / 60.)),
+            Gravity::from_profile(self.resources.movement.get(ProfileKey::Goblin)),
+            ProfileKey::Goblin,
+            OnGround::new(&mut self.resources, enemy_chandle),
             Hitbox::new(enemy_chandle),
             CombatStats::new(),
             AiControlled::new(),
-            HitMemory::new(),
+            Inbox::new(),
         ));
 
-        // setup camera to just follow player immediately, for now
-        self.resources.camera.target = Some(player_entity);
+        #[cfg(feature = "devtools")]
+        self.world
+            .insert_one(enemy_entity, crate::util::transition_log::TransitionLog::new())
+            .unwrap();
 
-        let body_entity_map = &mut self.resources.body_entity_map;
-        body_entity_map.insert(player_bhandle, player_entity);
-        body_entity_map.insert(enemy_bhandle, enemy_entity);
-        {
-            let tilemap = crate::map::tilemap::Tilemap::load().expect("Tilemap should exist here");
-
-            let grid = &tilemap.grid[..];
-            grid.chunks_exact(tilemap.width as usize).enumerate().for_each(|(column_id, row)| {
-                row.iter().enumerate().for_each(|(row_id, &value)| {
-                    if value > 0 {
-                        makeshift_static_platform(
-                            &mut self.resources,
-                            (row_id as f32 * 16. + 8., column_id as f32 * 16. + 8.),
-                            (8., 8.),
-                        );
-                    }
-                });
-            });
+        self.resources.body_entity_map.insert(enemy_bhandle, enemy_entity);
+        enemy_entity
+    }
+    /// Takes the current room from the start map and creates a collider for every solid tile,
+    /// replacing the previous ones. Does nothing if the map isn't loaded.
+    pub fn load_map(&mut self) -> Result<(), TilemapError> {
+        let (room, tilemap) = match self.assets.start_map() {
+            Some((def, level)) => (Room::from_ldtk(level), Tilemap::from_level(level, &def.layer)?),
+            None => return Ok(()),
+        };
+        self.resources.stage.set_room(room);
+        if let Some(entity) = self.resources.camera.target {
+            self.resources.events.publish(self::stage::RoomEnteredEvent { entity });
+        }
+
+        for chandle in std::mem::take(&mut self.map_colliders) {
+            remove_static_platform(&mut self.resources, chandle);
         }
+        self.build_map_colliders(&tilemap);
+        Ok(())
+    }
+    fn build_map_colliders(&mut self, tilemap: &Tilemap) {
+        let grid = &tilemap.grid[..];
+        grid.chunks_exact(tilemap.width as usize).enumerate().for_each(|(column_id, row)| {
+            row.iter().enumerate().for_each(|(row_id, &value)| {
+                if value > 0 {
+                    let chandle = makeshift_static_platform(
+                        &mut self.resources,
+                        (row_id as f32 * 16. + 8., column_id as f32 * 16. + 8.),
+                        (8., 8.),
+                    );
+                    self.map_colliders.push(chandle);
+                }
+            });
+        });
+    }
+    /// Reads the start map from its file again and rebuilds the room from it,
+    /// entities are left as they are
+    #[cfg(feature = "devtools")]
+    pub fn reload_map(&mut self) -> Result<(), AssetError> {
+        self.assets.reload_start_map()?;
+        self.load_map().map_err(AssetError::Map)
     }
     pub fn update(&mut self) {
-        // input should be updated on the main thread
-        self.resources.input_buttons.update();
-        schedule_execute(&mut self.world, &mut self.resources);
+        self.schedule.execute(&mut self.world, &mut self.resources);
+
+        #[cfg(feature = "devtools")]
+        {
+            self.profiler.record_update();
+            for system in self.schedule.systems().filter(|system| system.enabled) {
+                self.profiler.record_span(system.name, system.started, system.time);
+            }
+        }
     }
 }
 
-fn schedule_execute(world: &mut World, resources: &mut Resources) {
-    let mut cmd = CommandBuffer::new();
-
-    // // effect entities
-    crate::effect::effect_update_system(world, &mut cmd);
-    crate::effect::tint::tint_system(world);
-
-    crate::gfx::animation::animate_system(world, &resources.animations);
-    crate::phx::gravity_system(world);
-    crate::phx::ground_check_system(world, &resources.phys);
-    update_fsm_system(world, resources);
-    self::ai::update_fsm_system(world, resources);
-    crate::phx::resphys_sync_system(
-        world,
-        &mut resources.phys,
-        &mut resources.phys_bodies,
-        &mut resources.phys_colliders,
-    );
-    crate::game::combat::spread_pain_system(
-        &mut resources.hurt_queue,
-        &mut resources.damage_queue,
-        &resources.phys,
-        &resources.phys_bodies,
-        &resources.phys_colliders,
-        &resources.body_entity_map,
-    );
-    crate::phx::temp::reset_velocity_system(world, &resources.phys);
-    crate::game::combat::apply_damage_system(world, &mut resources.damage_queue, &mut cmd);
-
-    resources.camera.update(world, resources.stage.current_room());
-
-    // all new entities are created at frame end
-    cmd.run_on(world);
+fn build_schedule() -> Schedule {
+    use self::schedule::SystemStage::*;
+
+    let schedule = ScheduleBuilder::new()
+        // input should be updated on the main thread
+        .add_system(Input, "input", |_, resources, _| resources.input_buttons.update())
+        .add_system(Input, "begin_tick", |_, resources, _| resources.tick += 1)
+        // effect entities
+        .add_system(Logic, "effect_update", |world, _, cmd| {
+            crate::effect::effect_update_system(world, cmd)
+        })
+        .add_system(Logic, "afterimage", |world, _, cmd| {
+            crate::effect::afterimage::afterimage_system(world, cmd)
+        })
+        .add_system(Logic, "apply_effects", |world, _, _| {
+            crate::effect::apply_effects_system(world)
+        })
+        .add_system(Logic, "animate", |world, resources, _| {
+            crate::gfx::animation::animate_system(world, &resources.animations)
+        })
+        .add_system(Logic, "gravity", |world, _, _| crate::phx::gravity_system(world))
+        .add_system(Logic, "ground_check", |world, resources, _| {
+            crate::phx::ground_check_system(world, &resources.phys, &mut resources.events)
+        })
+        .add_system(Logic, "wall_check", |world, resources, _| {
+            crate::phx::wall_check_system(world, &resources.phys)
+        })
+        .add_system(Logic, "dash_cooldown", |world, resources, _| {
+            self::agent::controller::dash_cooldown_system(
+                world,
+                &resources.events,
+                &mut resources.dash_landing_reader,
+            )
+        })
+        .add_system(Logic, "horizontal_lock", |world, _, _| {
+            self::agent::controller::horizontal_lock_system(world)
+        })
+        .add_system(Logic, "landed_inbox", |world, resources, _| {
+            self::inbox::landed_inbox_system(
+                world,
+                &resources.events,
+                &mut resources.inbox_landing_reader,
+            )
+        })
+        .add_system(Logic, "inbox_delivery", |world, _, _| {
+            self::inbox::inbox_delivery_system(world)
+        })
+        .add_system(Logic, "player_fsm", |world, resources, _| update_fsm_system(world, resources))
+        .add_system(Logic, "ai_fsm", |world, resources, _| {
+            self::ai::update_fsm_system(world, resources)
+        })
+        .add_system(Physics, "resphys_sync", |world, resources, _| {
+            crate::phx::resphys_sync_system(
+                world,
+                &mut resources.phys,
+                &mut resources.phys_bodies,
+                &mut resources.phys_colliders,
+            )
+        })
+        .add_system(PostPhysics, "spread_pain", |_, resources, _| {
+            crate::game::combat::spread_pain_system(
+                &mut resources.events,
+                &resources.phys,
+                &resources.phys_bodies,
+                &resources.phys_colliders,
+                &resources.body_entity_map,
+            )
+        })
+        .add_system(PostPhysics, "reset_velocity", |world, resources, _| {
+            crate::phx::temp::reset_velocity_system(world, &resources.phys)
+        })
+        .add_system(PostPhysics, "apply_damage", |world, resources, cmd| {
+            crate::game::combat::apply_damage_system(
+                world,
+                &mut resources.events,
+                &mut resources.particles,
+                cmd,
+            )
+        })
+        // damage is applied in the tick it was dealt
+        .after("spread_pain")
+        .add_system(PostPhysics, "death", |world, resources, cmd| {
+            crate::game::combat::death_system(world, resources, cmd)
+        })
+        .after("apply_damage")
+        .add_system(Effects, "landing_dust", |world, resources, _| {
+            crate::particle::landing_dust_system(
+                world,
+                &resources.events,
+                &mut resources.landing_dust_reader,
+                &mut resources.particles,
+            )
+        })
+        .add_system(Effects, "landing_squash", |_, resources, cmd| {
+            crate::effect::scale::landing_squash_system(
+                &resources.events,
+                &mut resources.landing_squash_reader,
+                cmd,
+            )
+        })
+        .add_system(Effects, "emitter", |world, resources, _| {
+            crate::particle::emitter_system(world, &mut resources.particles)
+        })
+        .add_system(Effects, "particle_update", |_, resources, _| {
+            crate::particle::particle_update_system(&mut resources.particles)
+        })
+        .add_system(Effects, "room_entered", |world, resources, _| {
+            self::stage::room_entered_system(
+                world,
+                &resources.events,
+                &mut resources.room_entered_reader,
+                resources.stage.current_room(),
+            )
+        })
+        .add_system(Effects, "camera", |world, resources, _| {
+            resources.camera.update(world, resources.stage.current_room())
+        });
+    #[cfg(feature = "devtools")]
+    let schedule = schedule
+        .add_system(Logic, "reload_player_fsm", |world, resources, _| {
+            self::agent::controller::reload_fsm_system(world, resources)
+        })
+        .before("player_fsm");
+    schedule.build().unwrap_or_else(|errors| {
+        let errors: Vec<_> = errors.iter().map(|err| err.to_string()).collect();
+        panic!("invalid schedule: {}", errors.join(", "))
+    })
 }
 
-fn makeshift_static_platform(
+pub fn makeshift_static_platform(
     resources: &mut Resources,
     position: (f32, f32),
     shape: (f32, f32),
@@ -210,6 +429,12 @@ fn makeshift_static_platform(
     colliders.insert(collider.build(bhandle), bodies, physics).unwrap()
 }
 
+/// Removes the platform along with its body
+fn remove_static_platform(resources: &mut Resources, chandle: resphys::ColliderHandle) {
+    let bhandle = resources.phys_colliders[chandle].owner;
+    resources.phys_bodies.remove(bhandle, &mut resources.phys_colliders, &mut resources.phys);
+}
+
 fn makeshift_player_dynamic_collider(
     resources: &mut Resources,
 ) -> (resphys::BodyHandle, resphys::ColliderHandle) {
@@ -238,6 +463,7 @@ fn makeshift_player_dynamic_collider(
 
 fn makeshift_enemy_dynamic_collider(
     resources: &mut Resources,
+    position: glam::Vec2,
 ) -> (resphys::BodyHandle, resphys::ColliderHandle) {
     use crate::phx::{Category, ColliderTag};
     use glam::Vec2;
@@ -247,7 +473,7 @@ fn makeshift_enemy_dynamic_collider(
     let colliders = &mut resources.phys_colliders;
 
     let body = resphys::builder::BodyDesc::new()
-        .with_position(Vec2::new(100., 40.))
+        .with_position(position)
         // .self_collision(false)
         .build();
     let collider = resphys::builder::ColliderDesc::new(
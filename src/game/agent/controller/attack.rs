@@ -0,0 +1,35 @@
+use glam::Vec2;
+use hecs::{Entity, World};
+
+use crate::{
+    game::{combat::HurtInfo, resources::Resources},
+    gfx::Sprite,
+    phx::{Category, Position},
+    util::input::Button,
+};
+
+pub const ATTACK_DURATION: f32 = 0.1;
+
+// TRANSITIONS
+pub fn attack(_entity: Entity, _world: &World, resources: &Resources) -> bool {
+    resources.input_buttons.pressed(Button::Attack)
+}
+
+// STATES
+pub fn attack_on_enter(entity: Entity, world: &World, resources: &mut Resources) {
+    log::info!("Player attacks v2");
+
+    let mut query = world.query_one::<(&Position, &Sprite)>(entity).unwrap();
+    let (position, sprite) = query.get().unwrap();
+
+    let offset = if sprite.face_left { -16. } else { 16. };
+
+    let hurt_info = HurtInfo {
+        attacker: entity,
+        position: position.src + Vec2::new(offset, 0.),
+        half_exts: Vec2::new(8., 4.),
+        mask: Category::ENEMY.bits(),
+    };
+
+    resources.events.publish(hurt_info);
+}
@@ -0,0 +1,126 @@
+use hecs::{Entity, World};
+
+use crate::{
+    effect::afterimage::AfterimageTrail,
+    game::{combat::CombatStats, resources::Resources},
+    gfx::Sprite,
+    phx::{Gravity, LandedEvent, OnGround, Velocity},
+    util::events::{EventBus, EventReader},
+    util::input::Button,
+    FRAMETIME,
+};
+
+const DASH_DISTANCE: f32 = 48.;
+pub const DASH_DURATION: f32 = 0.15;
+const DASH_COOLDOWN: f32 = 0.4;
+/// ticks between taps of a direction that still count as double tap
+const DOUBLE_TAP_WINDOW: u32 = 12;
+
+/// Component, keeps track of the dash direction and its cooldown
+pub struct DashAbility {
+    /// time until dash can be used again, landing resets it
+    pub cooldown: f32,
+    pub direction: f32,
+    /// only a single dash is allowed between leaving the ground and landing
+    pub used_in_air: bool,
+}
+
+impl DashAbility {
+    pub fn new() -> Self {
+        Self { cooldown: 0., direction: 1., used_in_air: false }
+    }
+}
+
+pub fn dash_cooldown_system(
+    world: &mut World,
+    events: &EventBus,
+    reader: &mut EventReader<LandedEvent>,
+) {
+    for (_eid, dash) in world.query_mut::<&mut DashAbility>() {
+        dash.cooldown = (dash.cooldown - FRAMETIME).max(0.);
+    }
+    for LandedEvent { entity } in events.read(reader) {
+        if let Ok(mut dash) = world.get_mut::<DashAbility>(*entity) {
+            dash.cooldown = 0.;
+            dash.used_in_air = false;
+        }
+    }
+}
+
+// TRANSITIONS
+pub fn dash(entity: Entity, world: &World, resources: &Resources) -> bool {
+    let inputs = &resources.input_buttons;
+    let ready = world
+        .get::<DashAbility>(entity)
+        .map_or(false, |dash| dash.cooldown <= 0. && !dash.used_in_air);
+
+    ready
+        && (inputs.pressed(Button::Dash)
+            || inputs.double_tapped(Button::Left, DOUBLE_TAP_WINDOW)
+            || inputs.double_tapped(Button::Right, DOUBLE_TAP_WINDOW))
+}
+
+// STATES
+pub fn dash_on_enter(entity: Entity, world: &World, resources: &mut Resources) {
+    log::info!("Player dashing v2");
+    let inputs = &resources.input_buttons;
+
+    let mut query = world
+        .query_one::<(
+            &mut DashAbility,
+            &mut Velocity,
+            &mut Gravity,
+            &mut Sprite,
+            &mut CombatStats,
+            &mut AfterimageTrail,
+        )>(entity)
+        .unwrap();
+    let (dash, vel, gravity, sprite, combat_stats, trail) = query.get().unwrap();
+
+    let dir = (inputs.is_pressed(Button::Right) as i8) - (inputs.is_pressed(Button::Left) as i8);
+    dash.direction = match dir {
+        0 if sprite.face_left => -1.,
+        0 => 1.,
+        dir => dir as f32,
+    };
+    sprite.face_left = dash.direction < 0.;
+
+    dash.cooldown = DASH_COOLDOWN;
+    dash.used_in_air = !world.get::<OnGround>(entity).map_or(true, |ground| ground.on_ground);
+
+    vel.src.x = dash.direction * DASH_DISTANCE / DASH_DURATION;
+    vel.src.y = 0.;
+    gravity.set_enabled(false);
+    combat_stats.invulnerable = true;
+    trail.active = true;
+}
+
+pub fn dash_on_update(entity: Entity, world: &World, _resources: &mut Resources) {
+    let mut query = world.query_one::<(&DashAbility, &mut Velocity)>(entity).unwrap();
+    let (dash, vel) = query.get().unwrap();
+
+    // keep the speed constant, collisions might have changed it
+    vel.src.x = dash.direction * DASH_DISTANCE / DASH_DURATION;
+    vel.src.y = 0.;
+}
+
+pub fn dash_on_exit(entity: Entity, world: &World, _resources: &mut Resources) {
+    // momentum carried over after the dash ends
+    const EXIT_SPEED: f32 = 64.;
+
+    let mut query = world
+        .query_one::<(
+            &DashAbility,
+            &mut Velocity,
+            &mut Gravity,
+            &mut CombatStats,
+            &mut AfterimageTrail,
+        )>(entity)
+        .unwrap();
+    let (dash, vel, gravity, combat_stats, trail) = query.get().unwrap();
+
+    vel.src.x = dash.direction * EXIT_SPEED;
+    gravity.set_enabled(true);
+    combat_stats.invulnerable = false;
+    trail.active = false;
+}
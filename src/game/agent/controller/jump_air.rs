@@ -35,35 +35,17 @@ pub fn land(entity: Entity, world: &World, _resources: &Resources) -> bool {
 }
 
 // STATES
-pub fn jump_on_enter(entity: Entity, world: &World, _resources: &mut Resources) {
-    // full jump height: 56 pixels
-    // reach apex in: 0.5s
-    const INIT_VELOCITY: f32 = -224.0;
-
+pub fn jump_on_enter(entity: Entity, world: &World, resources: &mut Resources) {
     log::info!("Player jumping v2");
     let mut vel = world.get_mut::<Velocity>(entity).unwrap();
-    vel.src.y = INIT_VELOCITY;
-}
-
-pub fn jump_on_update(entity: Entity, world: &World, resources: &mut Resources) {
-    const TARGET_SPEED: f32 = 64.;
-    const ACCEL: f32 = 5.0;
-
-    super::movement::handle_movement(entity, world, resources, TARGET_SPEED, ACCEL);
+    vel.src.y = -resources.movement.of(entity, world).jump_velocity();
 }
 
-pub fn jump_on_exit(entity: Entity, world: &World, _resources: &mut Resources) {
+pub fn jump_on_exit(entity: Entity, world: &World, resources: &mut Resources) {
     let mut vel = world.get_mut::<Velocity>(entity).unwrap();
-    vel.src.y = vel.src.y.max(-128.);
+    vel.src.y = vel.src.y.max(-resources.movement.of(entity, world).jump_cut_velocity());
 }
 
 pub fn airtime_on_enter(_entity: Entity, _world: &World, _resources: &mut Resources) {
     log::info!("Player airtime v2");
 }
-
-pub fn airtime_on_update(entity: Entity, world: &World, resources: &mut Resources) {
-    const TARGET_SPEED: f32 = 64.;
-    const ACCEL: f32 = 5.0;
-
-    super::movement::handle_movement(entity, world, resources, TARGET_SPEED, ACCEL);
-}
@@ -1,79 +1,166 @@
+mod attack;
+mod dash;
 mod jump_air;
 mod movement;
+mod wall;
 
+use self::attack::{attack, attack_on_enter, ATTACK_DURATION};
+use self::dash::{dash, dash_on_enter, dash_on_exit, dash_on_update, DASH_DURATION};
+pub use self::dash::{dash_cooldown_system, DashAbility};
 use self::jump_air::{
-    airtime_on_enter, airtime_on_update, descending, jump, jump_held, jump_on_enter, jump_on_exit,
-    jump_on_update, land,
+    airtime_on_enter, descending, jump, jump_held, jump_on_enter, jump_on_exit, land,
 };
 use self::movement::{
-    idle_on_enter, idle_on_update, move_directional, run_on_enter, run_on_update,
+    handle_movement, idle_on_enter, idle_on_update, move_directional, run_on_enter, run_on_exit,
 };
+pub use self::movement::{horizontal_lock_system, HorizontalLock};
+use self::wall::{wall_jump_on_enter, wall_slide, wall_slide_on_enter, wall_slide_on_update};
 use crate::game::resources::Resources;
 use crate::phx::Velocity;
+use crate::util::state_machine::definition::{self, Registry};
 use crate::util::state_machine::{
-    and_condition, invert_condition, State, StateMachine, StateMachineBuilder,
+    always, condition, ContextFn, ParallelStateMachine, State, StateMachine, StateMachineBuilder,
+    Timing,
 };
-use hecs::{With, World};
+use crate::util::transition_log::TransitionLog;
+use hecs::{Entity, With, World};
+use serde::Deserialize;
 
-#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub const PLAYER_FSM_PATH: &str = "config/player_fsm.ron";
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Deserialize)]
 enum StateID {
     Idle,
     Run,
     Jump,
     Airtime,
+    WallSlide,
+    WallJump,
+    Dash,
+}
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+enum ActionID {
+    Ready,
+    Attack,
 }
 
 pub struct PlayerControlledV2 {
-    fsm: StateMachine,
+    fsm: ParallelStateMachine,
 }
 
 impl PlayerControlledV2 {
     pub fn new() -> Self {
-        let idle_state = State::new()
-            .on_enter(idle_on_enter)
-            .on_update(idle_on_update)
-            .add_transition(StateID::Run as usize, move_directional)
-            .add_transition(StateID::Jump as usize, jump)
-            .add_transition(StateID::Airtime as usize, invert_condition(land));
-
-        let run_state = State::new()
-            .on_enter(run_on_enter)
-            .on_update(run_on_update)
-            .add_transition(StateID::Idle as usize, invert_condition(move_directional))
-            .add_transition(StateID::Jump as usize, jump)
-            .add_transition(StateID::Airtime as usize, invert_condition(land));
-
-        let jump_state = State::new()
-            .on_enter(jump_on_enter)
-            .on_update(jump_on_update)
-            .on_exit(jump_on_exit)
-            .add_transition(StateID::Airtime as usize, descending)
-            .add_transition(StateID::Airtime as usize, invert_condition(jump_held));
-
-        let airtime_state = State::new()
-            .on_enter(airtime_on_enter)
-            .on_update(airtime_on_update)
-            .add_transition(
-                StateID::Idle as usize,
-                and_condition(land, invert_condition(move_directional)),
-            )
-            .add_transition(StateID::Run as usize, and_condition(land, move_directional));
-
-        let fsm = StateMachineBuilder::new()
-            .add_state(StateID::Idle as usize, idle_state)
-            .add_state(StateID::Run as usize, run_state)
-            .add_state(StateID::Jump as usize, jump_state)
-            .add_state(StateID::Airtime as usize, airtime_state)
-            .build(StateID::Idle as usize);
-
-        Self { fsm }
+        Self { fsm: build_fsm(locomotion()) }
+    }
+    pub fn to_dot(&self) -> String {
+        self.fsm.to_dot("player")
     }
 }
 
+/// Copy of the definition file from build time, used if the file can't be loaded at runtime
+const BUILTIN_PLAYER_FSM: &str = include_str!("../../../../config/player_fsm.ron");
+
+/// Locomotion is defined in a data file, the built-in copy is used if it can't be loaded
+fn locomotion() -> StateMachine<StateID> {
+    let registry = registry();
+    definition::load(PLAYER_FSM_PATH, &registry).unwrap_or_else(|err| {
+        log::warn!("Using built-in player state machine, {}", err);
+        definition::parse_def("built-in player state machine", BUILTIN_PLAYER_FSM)
+            .and_then(|def| definition::build(&def, &registry))
+            .expect("built-in player state machine is well defined")
+    })
+}
+
+/// Everything the locomotion definition file can refer to
+fn registry() -> Registry {
+    Registry::new()
+        .condition("dash", dash)
+        .condition("move_directional", move_directional)
+        .condition("jump", jump)
+        .condition("jump_held", jump_held)
+        .condition("descending", descending)
+        .condition("land", land)
+        .condition("wall_slide", wall_slide)
+        .duration("dash_duration", DASH_DURATION)
+        .action("idle_on_enter", idle_on_enter)
+        .action("idle_on_update", idle_on_update)
+        .action("run_on_enter", run_on_enter)
+        .action("run_on_exit", run_on_exit)
+        .action("jump_on_enter", jump_on_enter)
+        .action("jump_on_exit", jump_on_exit)
+        .action("airtime_on_enter", airtime_on_enter)
+        .action("wall_slide_on_enter", wall_slide_on_enter)
+        .action("wall_slide_on_update", wall_slide_on_update)
+        .action("wall_jump_on_enter", wall_jump_on_enter)
+        .action("dash_on_enter", dash_on_enter)
+        .action("dash_on_update", dash_on_update)
+        .action("dash_on_exit", dash_on_exit)
+        .action_with("handle_movement", |params| {
+            let target_speed = params.get("target_speed")?;
+            let acceleration = params.get("accel")?;
+            Ok(Box::new(move |entity: Entity, world: &World, resources: &mut Resources| {
+                handle_movement(entity, world, resources, target_speed, acceleration)
+            }) as ContextFn)
+        })
+}
+
+fn build_fsm(locomotion: StateMachine<StateID>) -> ParallelStateMachine {
+    // attacking doesn't interrupt moving, so it lives in its own region
+    let ready_state = State::new().add_transition(ActionID::Attack, condition!(attack));
+
+    let attack_state = State::new().on_enter(attack_on_enter).add_timed_transition(
+        ActionID::Ready,
+        Timing::After(ATTACK_DURATION),
+        condition!(always),
+    );
+
+    let action = StateMachineBuilder::new()
+        .add_state(ActionID::Ready, ready_state)
+        .add_state(ActionID::Attack, attack_state)
+        .build(ActionID::Ready)
+        .expect("player actions are well defined");
+
+    ParallelStateMachine::new().add_region(locomotion).add_region(action)
+}
+
 pub fn update_fsm_system(world: &mut World, resources: &mut Resources) {
     let mut query = world.query::<With<Velocity, &mut PlayerControlledV2>>();
 
     for (entity, pc) in query.iter() {
-        pc.fsm.update(entity, world, resources)
+        pc.fsm.update(entity, world, resources);
+
+        if let Ok(mut log) = world.get_mut::<TransitionLog>(entity) {
+            log.active = pc.fsm.describe();
+        }
+    }
+}
+
+/// Swaps the player's state machine for a fresh one once its definition file changes
+#[cfg(feature = "devtools")]
+pub fn reload_fsm_system(world: &mut World, resources: &mut Resources) {
+    if !resources.player_fsm_watcher.changed() {
+        return;
+    }
+
+    let registry = registry();
+    let def = match definition::load_def(PLAYER_FSM_PATH) {
+        Ok(def) => def,
+        Err(err) => {
+            log::warn!("Keeping the current player state machine, {}", err);
+            return;
+        }
+    };
+
+    let mut query = world.query::<&mut PlayerControlledV2>();
+    for (entity, pc) in query.iter() {
+        match definition::build(&def, &registry) {
+            Ok(locomotion) => {
+                pc.fsm.exit(entity, world, resources);
+                pc.fsm = build_fsm(locomotion);
+                log::info!("Reloaded {}", PLAYER_FSM_PATH);
+            }
+            Err(err) => log::warn!("Keeping the current player state machine, {}", err),
+        }
     }
 }
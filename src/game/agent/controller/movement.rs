@@ -3,6 +3,7 @@ use hecs::{Entity, World};
 use crate::{
     game::resources::Resources,
     gfx::{Animation, Sprite},
+    particle::Emitter,
     phx::Velocity,
     util::{input::Button, lerp},
     FRAMETIME,
@@ -10,6 +11,16 @@ use crate::{
 
 // TODO: Change the prints into debug-mode console/entity-state info
 
+/// Component, while the time is positive horizontal input is ignored by `handle_movement`
+pub struct HorizontalLock(pub f32);
+
+/// Counts the lock down in every state, not only the ones steering with `handle_movement`
+pub fn horizontal_lock_system(world: &mut World) {
+    for (_eid, lock) in world.query_mut::<&mut HorizontalLock>() {
+        lock.0 = (lock.0 - FRAMETIME).max(0.);
+    }
+}
+
 // TRANSITIONS
 pub fn move_directional(_entity: Entity, _world: &World, resources: &Resources) -> bool {
     let inputs = &resources.input_buttons;
@@ -27,6 +38,10 @@ pub fn handle_movement(
 ) {
     let inputs = &resources.input_buttons;
 
+    if world.get::<HorizontalLock>(entity).map_or(false, |lock| lock.0 > 0.) {
+        return;
+    }
+
     let mut query = world.query_one::<(&mut Velocity, &mut Sprite)>(entity).unwrap();
     let (vel, sprite) = query.get().unwrap();
 
@@ -69,11 +84,15 @@ pub fn run_on_enter(entity: Entity, world: &World, _resources: &mut Resources) {
     log::info!("Player walking v2");
     let mut animation = world.get_mut::<Animation>(entity).unwrap();
     animation.change("slimeu_run");
-}
 
-pub fn run_on_update(entity: Entity, world: &World, resources: &mut Resources) {
-    const TARGET_SPEED: f32 = 64.;
-    const ACCEL: f32 = 10.0;
+    if let Ok(mut emitter) = world.get_mut::<Emitter>(entity) {
+        emitter.active = true;
+        emitter.burst();
+    }
+}
 
-    handle_movement(entity, world, resources, TARGET_SPEED, ACCEL);
+pub fn run_on_exit(entity: Entity, world: &World, _resources: &mut Resources) {
+    if let Ok(mut emitter) = world.get_mut::<Emitter>(entity) {
+        emitter.active = false;
+    }
 }
@@ -0,0 +1,48 @@
+use hecs::{Entity, World};
+
+use crate::{
+    game::resources::Resources,
+    gfx::Sprite,
+    phx::{OnWall, Velocity},
+    util::input::Button,
+};
+
+use super::movement::HorizontalLock;
+
+// TRANSITIONS
+/// Touching a wall while pushing towards it
+pub fn wall_slide(entity: Entity, world: &World, resources: &Resources) -> bool {
+    let inputs = &resources.input_buttons;
+    let on_wall = world.get::<OnWall>(entity).unwrap();
+
+    (on_wall.left && inputs.is_pressed(Button::Left))
+        || (on_wall.right && inputs.is_pressed(Button::Right))
+}
+
+// STATES
+pub fn wall_slide_on_enter(_entity: Entity, _world: &World, _resources: &mut Resources) {
+    log::info!("Player wall sliding v2");
+}
+
+pub fn wall_slide_on_update(entity: Entity, world: &World, resources: &mut Resources) {
+    let mut vel = world.get_mut::<Velocity>(entity).unwrap();
+    vel.src.x = 0.;
+    vel.src.y = vel.src.y.min(resources.movement.of(entity, world).wall_slide_speed);
+}
+
+pub fn wall_jump_on_enter(entity: Entity, world: &World, resources: &mut Resources) {
+    log::info!("Player wall jumping v2");
+    let mut query = world
+        .query_one::<(&OnWall, &mut Velocity, &mut Sprite, &mut HorizontalLock)>(entity)
+        .unwrap();
+    let (on_wall, vel, sprite, lock) = query.get().unwrap();
+
+    let profile = resources.movement.of(entity, world);
+    let away = on_wall.away_direction();
+    vel.src.x = away * profile.wall_jump_push_speed;
+    vel.src.y = -profile.jump_velocity();
+    if away != 0. {
+        sprite.face_left = away < 0.;
+    }
+    lock.0 = profile.wall_jump_lock_time;
+}
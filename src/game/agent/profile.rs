@@ -0,0 +1,126 @@
+use hecs::{Entity, World};
+use serde::{Deserialize, Serialize};
+
+pub const MOVEMENT_CONFIG_PATH: &str = "config/movement.ron";
+
+/// Jump, fall and wall tuning, physics values are derived from what's easy to reason about.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MovementProfile {
+    /// height of a full jump in pixels
+    pub jump_height: f32,
+    /// seconds it takes a full jump to reach its apex
+    pub time_to_apex: f32,
+    /// height gained after releasing the jump button as early as possible
+    pub min_jump_height: f32,
+    /// multiplier of gravity while descending
+    pub fall_gravity_multiplier: f32,
+    /// maximum falling speed in pixels per second
+    pub terminal_velocity: f32,
+    /// seconds of suspended gravity at the apex of a jump
+    pub apex_hang_time: f32,
+    /// maximum falling speed while sliding down a wall
+    pub wall_slide_speed: f32,
+    /// horizontal speed away from the wall at the start of a wall jump
+    pub wall_jump_push_speed: f32,
+    /// seconds after a wall jump during which steering back into the wall is ignored
+    pub wall_jump_lock_time: f32,
+}
+
+impl Default for MovementProfile {
+    fn default() -> Self {
+        Self {
+            jump_height: 56.,
+            time_to_apex: 0.5,
+            min_jump_height: 18.3,
+            fall_gravity_multiplier: 1.,
+            terminal_velocity: 400.,
+            apex_hang_time: 0.,
+            wall_slide_speed: 48.,
+            wall_jump_push_speed: 96.,
+            wall_jump_lock_time: 0.15,
+        }
+    }
+}
+
+/// Component, which of the `MovementProfiles` the entity moves with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileKey {
+    Player,
+    Goblin,
+}
+
+/// Profiles of every kind of agent, as stored in the movement config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MovementProfiles {
+    pub player: MovementProfile,
+    pub goblin: MovementProfile,
+}
+
+impl MovementProfiles {
+    /// Profiles with values that can't be used are replaced with the default one
+    pub fn load() -> Self {
+        let mut profiles: Self = crate::util::config::load_or_default(MOVEMENT_CONFIG_PATH);
+        for (name, profile) in [("player", &mut profiles.player), ("goblin", &mut profiles.goblin)]
+        {
+            if let Err(err) = profile.validate() {
+                log::warn!("Using default {} movement profile, {}", name, err);
+                *profile = MovementProfile::default();
+            }
+        }
+        profiles
+    }
+    pub fn get(&self, key: ProfileKey) -> &MovementProfile {
+        match key {
+            ProfileKey::Player => &self.player,
+            ProfileKey::Goblin => &self.goblin,
+        }
+    }
+    /// Profile picked by the `ProfileKey` of the entity
+    pub fn of(&self, entity: Entity, world: &World) -> &MovementProfile {
+        self.get(*world.get::<ProfileKey>(entity).unwrap())
+    }
+}
+
+impl MovementProfile {
+    /// Zero or negative values would make the derived gravity infinite or point the wrong way
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = [
+            ("jump_height", self.jump_height),
+            ("time_to_apex", self.time_to_apex),
+            ("fall_gravity_multiplier", self.fall_gravity_multiplier),
+            ("terminal_velocity", self.terminal_velocity),
+            ("wall_slide_speed", self.wall_slide_speed),
+        ];
+        for (name, value) in positive {
+            if !(value.is_finite() && value > 0.) {
+                return Err(format!("{} should be positive, got {}", name, value));
+            }
+        }
+        let non_negative = [
+            ("min_jump_height", self.min_jump_height),
+            ("apex_hang_time", self.apex_hang_time),
+            ("wall_jump_push_speed", self.wall_jump_push_speed),
+            ("wall_jump_lock_time", self.wall_jump_lock_time),
+        ];
+        for (name, value) in non_negative {
+            if !(value.is_finite() && value >= 0.) {
+                return Err(format!("{} can't be negative, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+    /// Gravity acceleration in pixels per second squared
+    pub fn gravity(&self) -> f32 {
+        2. * self.jump_height / (self.time_to_apex * self.time_to_apex)
+    }
+    /// Upwards speed at the start of a jump
+    pub fn jump_velocity(&self) -> f32 {
+        2. * self.jump_height / self.time_to_apex
+    }
+    /// Upwards speed a jump is cut down to when the jump button is released
+    pub fn jump_cut_velocity(&self) -> f32 {
+        (2. * self.gravity() * self.min_jump_height).sqrt()
+    }
+}
@@ -1,114 +1,344 @@
+use glam::Vec2;
 use hecs::{Entity, With, World};
 
-use crate::phx::Velocity;
+use crate::game::agent::controller::PlayerControlledV2;
+use crate::game::combat::HurtInfo;
+use crate::gfx::Sprite;
+use crate::phx::{Category, Hitbox, Position, Velocity};
 use crate::util::lerp;
+use crate::util::state_machine::{
+    always, condition, Region, State, StateMachine, StateMachineBuilder, Timing,
+};
+use crate::util::transition_log::TransitionLog;
 use crate::FRAMETIME;
 
+use super::inbox::{received, AgentEvent};
 use super::resources::Resources;
 
-pub struct AiControlled {
-    state: AiState,
+const IDLE_TIME: f32 = 1.;
+const HURT_TIME: f32 = 2.;
+const WINDUP_TIME: f32 = 0.3;
+const ATTACK_TIME: f32 = 0.4;
+
+const PATROL_SPEED: f32 = 24.;
+const CHASE_SPEED: f32 = 48.;
+const ACCEL: f32 = 8.;
+
+/// distance at which the player gets noticed
+const SIGHT_RANGE: f32 = 96.;
+/// distance at which the player is forgotten, bigger than `SIGHT_RANGE` to not flicker between states
+const LOSE_RANGE: f32 = 128.;
+const ATTACK_RANGE: f32 = 20.;
+/// vertical distance at which the player can be attacked
+const ATTACK_HEIGHT: f32 = 12.;
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+enum StateID {
+    /// parent of the behaviours, leaving it interrupts whichever one was running
+    Active,
+    Hurt,
 }
 
-// entities with this component want to remember getting hit
-// TODO: should be replaced with generic event when FSM is reworked
-#[derive(Debug, Clone)]
-pub struct HitMemory(pub bool);
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+enum BehaviourID {
+    Idle,
+    Patrol,
+    Chase,
+    Windup,
+    Attack,
+}
 
-impl HitMemory {
-    pub fn new() -> Self {
-        Self(false)
-    }
+pub struct AiControlled {
+    fsm: StateMachine<StateID>,
 }
 
 impl AiControlled {
     pub fn new() -> Self {
-        Self { state: AiState::Idle }
-    }
-}
+        let idle_state = State::new()
+            .on_enter(idle_on_enter)
+            .on_update(decelerate_on_update)
+            .add_transition(BehaviourID::Chase, condition!(sees_player(SIGHT_RANGE)))
+            .add_timed_transition(
+                BehaviourID::Patrol,
+                Timing::After(IDLE_TIME),
+                condition!(always),
+            );
 
-enum AiState {
-    Idle,
-    Hurt(f32),
+        let patrol_state = State::new()
+            .on_enter(patrol_on_enter)
+            .on_update(patrol_on_update)
+            .add_transition(BehaviourID::Chase, condition!(sees_player(SIGHT_RANGE)));
+
+        let chase_state = State::new()
+            .on_enter(chase_on_enter)
+            .on_update(chase_on_update)
+            .add_transition(BehaviourID::Patrol, condition!(sees_player(LOSE_RANGE)).not())
+            .add_transition(BehaviourID::Windup, condition!(in_attack_range));
+
+        let windup_state = State::new()
+            .on_enter(windup_on_enter)
+            .on_update(decelerate_on_update)
+            .add_timed_transition(
+                BehaviourID::Attack,
+                Timing::After(WINDUP_TIME),
+                condition!(always),
+            );
+
+        let attack_state = State::new()
+            .on_enter(attack_on_enter)
+            .on_update(decelerate_on_update)
+            .add_timed_transition(
+                BehaviourID::Chase,
+                Timing::After(ATTACK_TIME),
+                condition!(always),
+            );
+
+        let behaviours = StateMachineBuilder::new()
+            .add_state(BehaviourID::Idle, idle_state)
+            .add_state(BehaviourID::Patrol, patrol_state)
+            .add_state(BehaviourID::Chase, chase_state)
+            .add_state(BehaviourID::Windup, windup_state)
+            .add_state(BehaviourID::Attack, attack_state)
+            .build(BehaviourID::Idle)
+            .expect("enemy behaviours are well defined");
+
+        // getting hit interrupts any behaviour
+        let active_state = State::new()
+            .add_region(behaviours)
+            .add_transition(StateID::Hurt, condition!(received(AgentEvent::Hit)));
+
+        // getting hit again restarts the hurt time,
+        // and so does landing, so the time spent knocked into the air doesn't shorten it
+        let hurt_state = State::new()
+            .on_enter(hurt_on_enter)
+            .on_update(hurt_on_update)
+            .restart_on(condition!(received(AgentEvent::Hit)))
+            .restart_on(condition!(received(AgentEvent::Landed)))
+            .add_timed_transition(StateID::Active, Timing::After(HURT_TIME), condition!(always));
+
+        let fsm = StateMachineBuilder::new()
+            .add_state(StateID::Active, active_state)
+            .add_state(StateID::Hurt, hurt_state)
+            .build(StateID::Active)
+            .expect("enemy state machine is well defined");
+
+        Self { fsm }
+    }
+    pub fn to_dot(&self) -> String {
+        self.fsm.to_dot("enemy")
+    }
 }
 
 pub fn update_fsm_system(world: &mut World, resources: &mut Resources) {
     let mut query = world.query::<With<Velocity, &mut AiControlled>>();
 
     for (entity, ai_controlled) in query.iter() {
-        if let Some(transition) = ai_controlled.state.update(entity, world, resources) {
-            ai_controlled.state = transition;
-            ai_controlled.state.on_enter(entity, world, resources);
+        ai_controlled.fsm.update(entity, world, resources);
+
+        if let Ok(mut log) = world.get_mut::<TransitionLog>(entity) {
+            log.active = ai_controlled.fsm.describe();
         }
     }
 }
 
-impl AiState {
-    fn update(&mut self, entity: Entity, world: &World, resources: &Resources) -> Option<Self> {
-        match self {
-            Self::Idle => idle_update(entity, world, resources),
-            Self::Hurt(timer) => hurt_update(entity, world, timer, resources),
-        }
-    }
-    fn on_enter(&mut self, entity: Entity, world: &World, _resources: &mut Resources) {
-        match self {
-            Self::Idle => idle_on_enter(entity, world),
-            Self::Hurt(_timer) => hurt_on_enter(entity, world),
-        }
+// TRANSITIONS
+fn sees_player(range: f32) -> impl Fn(Entity, &World, &Resources) -> bool + 'static + Send + Sync {
+    move |entity: Entity, world: &World, resources: &Resources| {
+        spot_player(entity, world, resources, range).is_some()
     }
 }
 
-fn idle_update(entity: Entity, world: &World, _resources: &Resources) -> Option<AiState> {
-    const DECEL: f32 = 20.;
+fn in_attack_range(entity: Entity, world: &World, resources: &Resources) -> bool {
+    spot_player(entity, world, resources, LOSE_RANGE).map_or(false, |to_player| {
+        to_player.x.abs() <= ATTACK_RANGE && to_player.y.abs() <= ATTACK_HEIGHT
+    })
+}
+
+// STATES
+fn idle_on_enter(_entity: Entity, _world: &World, _resources: &mut Resources) {
+    log::info!("Enemy idle");
+}
 
-    let mut query_o = world.query_one::<(&mut HitMemory, &mut Velocity)>(entity).unwrap();
-    let (HitMemory(is_hit), Velocity { src: vel }) = query_o.get().unwrap();
+fn patrol_on_enter(_entity: Entity, _world: &World, _resources: &mut Resources) {
+    log::info!("Enemy patrols");
+}
 
-    vel.x = lerp(0., vel.x, f32::exp2(-DECEL * FRAMETIME));
+fn patrol_on_update(entity: Entity, world: &World, resources: &mut Resources) {
+    let mut direction = facing(entity, world);
+    let surroundings = Surroundings::sense(entity, world, resources, direction);
 
-    if vel.x.abs() < 1. {
-        vel.x = 0.;
+    if surroundings.grounded && !surroundings.can_walk_ahead() {
+        direction = -direction;
+        face(entity, world, direction);
     }
 
-    if *is_hit {
-        *is_hit = false;
-        return Some(AiState::Hurt(2.));
-    }
+    walk(entity, world, direction * PATROL_SPEED);
+}
 
-    None
+fn chase_on_enter(_entity: Entity, _world: &World, _resources: &mut Resources) {
+    log::info!("Enemy chases");
 }
 
-fn idle_on_enter(_entity: Entity, _world: &World) {
-    log::info!("Enemy idle");
+fn chase_on_update(entity: Entity, world: &World, resources: &mut Resources) {
+    let to_player = match spot_player(entity, world, resources, LOSE_RANGE) {
+        Some(to_player) => to_player,
+        None => return,
+    };
+
+    let direction = to_player.x.signum();
+    face(entity, world, direction);
+
+    // don't follow the player off ledges or into walls
+    let surroundings = Surroundings::sense(entity, world, resources, direction);
+    let target_speed = if surroundings.can_walk_ahead() { direction * CHASE_SPEED } else { 0. };
+    walk(entity, world, target_speed);
+}
+
+fn windup_on_enter(_entity: Entity, _world: &World, _resources: &mut Resources) {
+    log::info!("Enemy prepares to attack");
+}
+
+fn attack_on_enter(entity: Entity, world: &World, resources: &mut Resources) {
+    log::info!("Enemy attacks");
+
+    let position = world.get::<Position>(entity).unwrap().src;
+    let offset = Vec2::new(facing(entity, world) * 12., 4.);
+
+    let hurt_info = HurtInfo {
+        attacker: entity,
+        position: position + offset,
+        half_exts: Vec2::new(8., 4.),
+        mask: Category::PLAYER.bits(),
+    };
+
+    resources.events.publish(hurt_info);
+}
+
+/// Shared by states that stand still until they time out
+fn decelerate_on_update(entity: Entity, world: &World, _resources: &mut Resources) {
+    const DECEL: f32 = 20.;
+
+    decelerate(entity, world, DECEL);
+}
+
+fn hurt_on_enter(_entity: Entity, _world: &World, _resources: &mut Resources) {
+    log::info!("Enemy got hit");
 }
 
-fn hurt_update(
-    entity: Entity,
-    world: &World,
-    timer: &mut f32,
-    _resources: &Resources,
-) -> Option<AiState> {
+fn hurt_on_update(entity: Entity, world: &World, _resources: &mut Resources) {
     const DECEL: f32 = 10.0;
 
-    let mut query_o = world.query_one::<(&mut HitMemory, &mut Velocity)>(entity).unwrap();
-    let (HitMemory(is_hit), Velocity { src: vel }) = query_o.get().unwrap();
-    *is_hit = false;
+    decelerate(entity, world, DECEL);
+}
+
+// HELPERS
 
-    *timer -= FRAMETIME;
+fn decelerate(entity: Entity, world: &World, decel: f32) {
+    let mut vel = world.get_mut::<Velocity>(entity).unwrap();
 
-    vel.x = lerp(0., vel.x, f32::exp2(-DECEL * FRAMETIME));
+    vel.src.x = lerp(0., vel.src.x, f32::exp2(-decel * FRAMETIME));
 
-    if vel.x.abs() < 1. {
-        vel.x = 0.;
+    if vel.src.x.abs() < 1. {
+        vel.src.x = 0.;
     }
+}
+
+fn walk(entity: Entity, world: &World, target_speed: f32) {
+    let mut vel = world.get_mut::<Velocity>(entity).unwrap();
 
-    if *timer <= 0. {
-        return Some(AiState::Idle);
+    vel.src.x = lerp(target_speed, vel.src.x, f32::exp2(-ACCEL * FRAMETIME));
+}
+
+/// 1 when facing right, -1 when facing left
+fn facing(entity: Entity, world: &World) -> f32 {
+    let sprite = world.get::<Sprite>(entity).unwrap();
+    if sprite.face_left {
+        -1.
+    } else {
+        1.
     }
+}
 
-    None
+fn face(entity: Entity, world: &World, direction: f32) {
+    let mut sprite = world.get_mut::<Sprite>(entity).unwrap();
+    if direction != 0. {
+        sprite.face_left = direction < 0.;
+    }
 }
 
-fn hurt_on_enter(_entity: Entity, _world: &World) {
-    log::info!("Enemy got hit");
+/// Offset to the player if they are within range and not hidden behind level geometry
+fn spot_player(entity: Entity, world: &World, resources: &Resources, range: f32) -> Option<Vec2> {
+    let position = world.get::<Position>(entity).unwrap().src;
+
+    let mut query = world.query::<With<PlayerControlledV2, &Position>>();
+    let (_player, player_position) = query.iter().next()?;
+    let to_player = player_position.src - position;
+
+    if to_player.length() > range {
+        return None;
+    }
+
+    line_of_sight(position, player_position.src, resources).then(|| to_player)
+}
+
+/// Checks if no level geometry is in between by testing small boxes along the line
+fn line_of_sight(from: Vec2, to: Vec2, resources: &Resources) -> bool {
+    const STEP: f32 = 4.;
+
+    let distance = (to - from).length();
+    let steps = (distance / STEP).ceil() as usize;
+
+    (1..steps).all(|step| {
+        let point = from.lerp(to, step as f32 / steps as f32);
+        !overlaps_ground(point, Vec2::new(0.5, 0.5), resources)
+    })
+}
+
+fn overlaps_ground(position: Vec2, half_exts: Vec2, resources: &Resources) -> bool {
+    resources
+        .phys
+        .overlap_test(
+            position,
+            half_exts,
+            Category::GROUND.bits(),
+            &resources.phys_bodies,
+            &resources.phys_colliders,
+        )
+        .into_iter()
+        .next()
+        .is_some()
+}
+
+/// Level geometry around the collider, found with overlap queries
+struct Surroundings {
+    /// standing on the ground
+    grounded: bool,
+    /// wall right in front
+    wall_ahead: bool,
+    /// ground in front to step onto
+    ground_ahead: bool,
+}
+
+impl Surroundings {
+    fn sense(entity: Entity, world: &World, resources: &Resources, direction: f32) -> Self {
+        let position = world.get::<Position>(entity).unwrap().src;
+        let hitbox = world.get::<Hitbox>(entity).unwrap();
+        let collider = &resources.phys_colliders[hitbox.src];
+
+        let center = position + collider.offset;
+        let half_exts = collider.shape.half_exts;
+
+        let below = center + Vec2::new(0., half_exts.y + 0.5);
+        let ahead = center + Vec2::new(direction * (half_exts.x + 0.5), 0.);
+        let ahead_below = center + Vec2::new(direction * (half_exts.x + 0.5), half_exts.y + 0.5);
+
+        Self {
+            grounded: overlaps_ground(below, Vec2::new(half_exts.x - 0.5, 0.5), resources),
+            wall_ahead: overlaps_ground(ahead, Vec2::new(0.5, half_exts.y - 0.5), resources),
+            ground_ahead: overlaps_ground(ahead_below, Vec2::new(0.5, 0.5), resources),
+        }
+    }
+    fn can_walk_ahead(&self) -> bool {
+        !self.wall_ahead && self.ground_ahead
+    }
 }
@@ -1,6 +1,9 @@
-use super::ai::HitMemory;
-use crate::effect::{tint::TintChange, EffectData};
-use crate::phx::{BodySet, ColliderSet, PhysicsWorld, Velocity};
+use super::inbox::{AgentEvent, Inbox};
+use super::resources::Resources;
+use crate::effect::{blink::Blink, shake::Shake, tint::TintChange, EffectData};
+use crate::particle::Particles;
+use crate::phx::{BodySet, ColliderSet, Hitbox, PhysicsWorld, Position, Velocity};
+use crate::util::events::EventBus;
 use glam::Vec2;
 use hecs::{CommandBuffer, Entity, World};
 use macroquad::color::Color;
@@ -15,50 +18,54 @@ pub struct CombatStats {
     pub kb_force: Vec2,
     /// knockback resistance
     pub kb_res: f32,
+    /// ignores all incoming damage while set
+    pub invulnerable: bool,
 }
 
 impl CombatStats {
     pub fn new() -> Self {
-        Self { kb_force: Vec2::new(64., -64.), kb_res: 0.5 }
+        Self { kb_force: Vec2::new(64., -64.), kb_res: 0.5, invulnerable: false }
     }
 }
 
+/// Published when an entity is killed, it's despawned in the same tick.
+/// Nothing has health yet, so only the devtools console kills.
+#[derive(Debug, Clone)]
+pub struct DeathEvent {
+    pub entity: Entity,
+}
+
 #[derive(Debug, Clone)]
 pub struct DamageEvent {
     pub input: Entity,
     pub output: Entity,
 }
 
-pub struct DamageQueue {
-    events: Vec<DamageEvent>,
-}
-
-impl DamageQueue {
-    pub fn new() -> Self {
-        Self { events: Vec::with_capacity(128) }
-    }
-    pub fn push(&mut self, damage_event: DamageEvent) {
-        self.events.push(damage_event);
-    }
-}
-
 pub fn apply_damage_system(
     world: &mut World,
-    damage_queue: &mut DamageQueue,
+    events: &mut EventBus,
+    particles: &mut Particles,
     command_buffer: &mut CommandBuffer,
 ) {
-    for DamageEvent { input, output } in damage_queue.events.drain(..) {
+    for DamageEvent { input, output } in events.claim::<DamageEvent>() {
         log::debug!("A DamageEvent arrived succesfully from {:?} and hit {:?}", input, output);
 
         let maybe_off_combat = world.get_mut::<CombatStats>(input).ok().map(|x| x.clone());
 
-        if let Ok((maybe_hit_memory, maybe_velocity, def_combat)) =
-            world.query_one_mut::<(Option<&mut HitMemory>, Option<&mut Velocity>, &CombatStats)>(
-                output,
-            )
+        if let Ok((maybe_inbox, maybe_velocity, maybe_position, def_combat)) = world
+            .query_one_mut::<(
+                Option<&mut Inbox>,
+                Option<&mut Velocity>,
+                Option<&Position>,
+                &CombatStats,
+            )>(output)
         {
-            if let Some(HitMemory(hit_state)) = maybe_hit_memory {
-                *hit_state = true;
+            if def_combat.invulnerable {
+                continue;
+            }
+
+            if let Some(inbox) = maybe_inbox {
+                inbox.send(AgentEvent::Hit);
             }
 
             let knockback = if let Some(off_combat) = maybe_off_combat {
@@ -72,11 +79,33 @@ pub fn apply_damage_system(
                 velocity.src = knockback;
             }
 
+            if let Some(position) = maybe_position {
+                particles.emit("hit_spark", position.src, 8);
+            }
+
             command_buffer.spawn((
-                EffectData { parent: output, duration: 0.15 },
+                EffectData::new(output, 0.15),
                 TintChange::new(macroquad::color_u8!(255, 36, 0, 192)),
             ));
+            command_buffer.spawn((EffectData::new(output, 0.15), Shake::new(1.5, 20.)));
+            command_buffer.spawn((EffectData::new(output, 0.3), Blink::new(0.05)));
+        }
+    }
+}
+
+/// Despawns killed entities along with their physics bodies
+pub fn death_system(world: &World, resources: &mut Resources, command_buffer: &mut CommandBuffer) {
+    for DeathEvent { entity } in resources.events.claim::<DeathEvent>() {
+        if let Ok(hitbox) = world.get::<Hitbox>(entity) {
+            let bhandle = resources.phys_colliders[hitbox.src].owner;
+            resources.body_entity_map.remove(&bhandle);
+            resources.phys_bodies.remove(
+                bhandle,
+                &mut resources.phys_colliders,
+                &mut resources.phys,
+            );
         }
+        command_buffer.despawn(entity);
     }
 }
 
@@ -96,32 +125,15 @@ pub struct HurtInfo {
     // report success to attacker - tricky, not necessary right now
 }
 
-// TODO: Keep copy_msgs for feature devmode only
-pub struct HurtQueue {
-    msgs: Vec<HurtInfo>,
-    pub copy_msgs: Vec<HurtInfo>,
-}
-
-impl HurtQueue {
-    pub fn new() -> Self {
-        Self { msgs: Vec::with_capacity(128), copy_msgs: Vec::with_capacity(128) }
-    }
-    pub fn push(&mut self, hurt_info: HurtInfo) {
-        self.msgs.push(hurt_info);
-    }
-}
-
 /// This system is responsible for checking checking which entities are supposed to get hurt and informing them.
 pub fn spread_pain_system(
-    hurt_queue: &mut HurtQueue,
-    damage_queue: &mut DamageQueue,
+    events: &mut EventBus,
     phys_world: &PhysicsWorld,
     bodies: &BodySet,
     colliders: &ColliderSet,
     body_entity_map: &crate::phx::BodyEntityMap,
 ) {
-    hurt_queue.copy_msgs.clear();
-    for hurt_info in &hurt_queue.msgs {
+    for hurt_info in events.claim::<HurtInfo>() {
         let hits = phys_world.overlap_test(
             hurt_info.position,
             hurt_info.half_exts,
@@ -131,9 +143,8 @@ pub fn spread_pain_system(
         );
         for chandle in hits {
             if let Some(defender) = body_entity_map.get(&colliders[chandle].owner) {
-                damage_queue.push(DamageEvent { input: hurt_info.attacker, output: *defender });
+                events.publish(DamageEvent { input: hurt_info.attacker, output: *defender });
             }
         }
     }
-    std::mem::swap(&mut hurt_queue.copy_msgs, &mut hurt_queue.msgs);
 }
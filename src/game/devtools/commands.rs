@@ -0,0 +1,323 @@
+/*!
+Commands of the devtools console, a line is split on whitespace into a command and its arguments.
+*/
+use glam::Vec2;
+use hecs::{Entity, EntityRef, World};
+
+use super::super::agent::controller::PlayerControlledV2;
+use super::super::combat::{CombatStats, DeathEvent};
+use super::{cursor_position, Game};
+use crate::phx::{Position, Velocity};
+use crate::util::bindings::PhysicalInput;
+use crate::util::input::Button;
+
+const HELP: &[&str] = &[
+    "help                          this list",
+    "spawn <goblin|platform>       at the cursor",
+    "tp [x y]                      player to the position or the cursor",
+    "stats <field> <value> [id]    CombatStats of the entity or the player",
+    "  fields: kb_force_x, kb_force_y, kb_res, invulnerable",
+    "overlay <colliders|hurtboxes|fsm> [on|off]",
+    "entities                      list entities and their components",
+    "kill <id>                     publish a death event for the entity, not the player",
+    "inspect [id]                  open the inspector, with the entity selected",
+    "reload map",
+    "time <scale|pause|resume|step>",
+    "system [name [on|off]]        list systems, or toggle one",
+    "bind [button [input]]         list bindings or add one, inputs are written as in the config",
+    "  e.g. bind Jump Key(\"C\")",
+    "unbind <button> <input>",
+    "rebind <button> <input|next>  replace the inputs, next waits for a press once closed",
+    "bind save                     write the bindings to the input config",
+];
+
+/// Runs the line, output goes to the console
+pub fn run(game: &mut Game, line: &str) -> Result<(), String> {
+    let mut args = line.split_whitespace();
+    let command = match args.next() {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+    let args: Vec<&str> = args.collect();
+
+    match (command, args.as_slice()) {
+        ("help", []) => {
+            for line in HELP {
+                game.console.print(*line);
+            }
+        }
+        ("spawn", [name]) => {
+            let position = cursor_position(game);
+            let entity = spawn(game, name, position)?;
+            if let Some(entity) = entity {
+                game.console.print(format!("spawned {} as {}", name, entity.id()));
+            }
+        }
+        ("tp", []) => {
+            let position = cursor_position(game);
+            teleport_player(game, position)?;
+        }
+        ("tp", [x, y]) => {
+            let position = Vec2::new(parse(x)?, parse(y)?);
+            teleport_player(game, position)?;
+        }
+        ("stats", [field, value]) => {
+            let entity = player(&game.world)?;
+            set_stat(&game.world, entity, field, value)?;
+        }
+        ("stats", [field, value, id]) => {
+            let entity = find_entity(&game.world, parse(id)?)?;
+            set_stat(&game.world, entity, field, value)?;
+        }
+        ("overlay", [name, rest @ ..]) => {
+            let overlay = match *name {
+                "colliders" => &mut game.overlays.colliders,
+                "hurtboxes" => &mut game.overlays.hurtboxes,
+                "fsm" => &mut game.overlays.state_machines,
+                _ => return Err(format!("unknown overlay {}", name)),
+            };
+            *overlay = match rest {
+                [] => !*overlay,
+                ["on"] => true,
+                ["off"] => false,
+                _ => return Err("expected on or off".to_owned()),
+            };
+            let state = if *overlay { "on" } else { "off" };
+            game.console.print(format!("{} overlay {}", name, state));
+        }
+        ("entities", []) => {
+            let lines: Vec<_> = game
+                .world
+                .iter()
+                .map(|entity| {
+                    format!("{:>4} {}", entity.entity().id(), component_names(&entity).join(", "))
+                })
+                .collect();
+            for line in lines {
+                game.console.print(line);
+            }
+        }
+        ("kill", [id]) => {
+            let entity = find_entity(&game.world, parse(id)?)?;
+            if game.world.get::<PlayerControlledV2>(entity).is_ok() {
+                return Err("the player can't die yet".to_owned());
+            }
+            game.resources.events.publish(DeathEvent { entity });
+            game.console.print(format!("killed {}", entity.id()));
+        }
+        ("inspect", []) => game.inspector.open = true,
+        ("inspect", [id]) => {
+            let entity = find_entity(&game.world, parse(id)?)?;
+            game.inspector.open = true;
+            game.inspector.selected = Some(entity);
+        }
+        ("reload", ["map"]) => {
+            game.reload_map().map_err(|err| err.to_string())?;
+            game.console.print("map reloaded");
+        }
+        ("time", ["pause"]) => game.time_control.paused = true,
+        ("time", ["resume"]) => game.time_control.paused = false,
+        ("time", ["step"]) => {
+            game.time_control.paused = true;
+            game.update();
+        }
+        ("time", [scale]) => {
+            let scale: f64 = parse(scale)?;
+            if scale <= 0. || !scale.is_finite() {
+                return Err("time scale should be positive".to_owned());
+            }
+            game.time_control.scale = scale;
+            game.console.print(format!("time scale x{}", scale));
+        }
+        ("system", []) => {
+            let lines: Vec<_> = game
+                .schedule
+                .systems()
+                .map(|system| {
+                    let state = if system.enabled { "on" } else { "off" };
+                    format!("{:<20} {:<12} {}", system.name, format!("{:?}", system.stage), state)
+                })
+                .collect();
+            for line in lines {
+                game.console.print(line);
+            }
+        }
+        ("system", [name, rest @ ..]) => {
+            let system = game.schedule.systems().find(|system| system.name == *name);
+            let enabled = system.ok_or_else(|| format!("unknown system {}", name))?.enabled;
+            let enabled = match rest {
+                [] => !enabled,
+                ["on"] => true,
+                ["off"] => false,
+                _ => return Err("expected on or off".to_owned()),
+            };
+            game.schedule.set_enabled(name, enabled);
+            let state = if enabled { "on" } else { "off" };
+            game.console.print(format!("system {} {}", name, state));
+        }
+        ("bind", []) => {
+            let lines: Vec<_> = game
+                .resources
+                .input_buttons
+                .bindings()
+                .buttons
+                .iter()
+                .map(|(button, inputs)| format!("{:?}: {:?}", button, inputs))
+                .collect();
+            for line in lines {
+                game.console.print(line);
+            }
+        }
+        ("bind", ["save"]) => game.resources.input_buttons.save_bindings(),
+        ("bind", [button]) => {
+            let button = parse_button(button)?;
+            let inputs = &game.resources.input_buttons.bindings().buttons[button];
+            game.console.print(format!("{:?}: {:?}", button, inputs));
+        }
+        ("bind", [button, input @ ..]) => {
+            let (button, input) = (parse_button(button)?, parse_input(input)?);
+            game.resources.input_buttons.bind(button, input);
+            game.console.print(format!("{:?} bound to {:?}", button, input));
+        }
+        ("unbind", [button, input @ ..]) if !input.is_empty() => {
+            let (button, input) = (parse_button(button)?, parse_input(input)?);
+            game.resources.input_buttons.unbind(button, input);
+            game.console.print(format!("{:?} unbound from {:?}", button, input));
+        }
+        ("rebind", [button, "next"]) => {
+            let button = parse_button(button)?;
+            game.rebinding = Some(button);
+            game.console
+                .print(format!("close the console and press the new input for {:?}", button));
+        }
+        ("rebind", [button, input @ ..]) if !input.is_empty() => {
+            let (button, input) = (parse_button(button)?, parse_input(input)?);
+            game.resources.input_buttons.rebind(button, input);
+            game.console.print(format!("{:?} bound to {:?} only", button, input));
+        }
+        (
+            "help" | "spawn" | "tp" | "stats" | "overlay" | "entities" | "kill" | "inspect"
+            | "reload" | "time" | "system" | "bind" | "unbind" | "rebind",
+            _,
+        ) => return Err(format!("wrong arguments for {}, see help", command)),
+        _ => return Err(format!("unknown command {}, see help", command)),
+    }
+    Ok(())
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("can't parse {}", value))
+}
+
+/// Buttons are named as in the input config, e.g. Jump
+fn parse_button(name: &str) -> Result<Button, String> {
+    ron::from_str(name).map_err(|_| format!("unknown button {}", name))
+}
+
+/// Written as in the input config, e.g. Axis(axis: LeftStickX, positive: true)
+fn parse_input(words: &[&str]) -> Result<PhysicalInput, String> {
+    ron::from_str(&words.join(" ")).map_err(|err| format!("can't parse input, {}", err))
+}
+
+fn spawn(game: &mut Game, name: &str, position: Vec2) -> Result<Option<Entity>, String> {
+    match name {
+        "goblin" => Ok(Some(game.spawn_enemy(position))),
+        "platform" => {
+            super::super::makeshift_static_platform(
+                &mut game.resources,
+                (position.x, position.y),
+                (8., 8.),
+            );
+            game.console.print("spawned platform");
+            Ok(None)
+        }
+        _ => Err(format!("can't spawn {}, try goblin or platform", name)),
+    }
+}
+
+fn teleport_player(game: &mut Game, position: Vec2) -> Result<(), String> {
+    let entity = player(&game.world)?;
+    if let Ok(mut current) = game.world.get_mut::<Position>(entity) {
+        current.src = position;
+    }
+    if let Ok(mut velocity) = game.world.get_mut::<Velocity>(entity) {
+        velocity.src = Vec2::ZERO;
+    }
+    game.console.print(format!("player moved to {} {}", position.x, position.y));
+    Ok(())
+}
+
+fn player(world: &World) -> Result<Entity, String> {
+    let mut query = world.query::<&PlayerControlledV2>();
+    let player = query.iter().next().map(|(entity, _)| entity);
+    player.ok_or_else(|| "there's no player".to_owned())
+}
+
+fn find_entity(world: &World, id: u32) -> Result<Entity, String> {
+    let entity = world.iter().map(|entity| entity.entity()).find(|entity| entity.id() == id);
+    entity.ok_or_else(|| format!("there's no entity {}", id))
+}
+
+fn set_stat(world: &World, entity: Entity, field: &str, value: &str) -> Result<(), String> {
+    let mut stats = world
+        .get_mut::<CombatStats>(entity)
+        .map_err(|_| format!("entity {} has no CombatStats", entity.id()))?;
+    match field {
+        "kb_force_x" => stats.kb_force.x = parse(value)?,
+        "kb_force_y" => stats.kb_force.y = parse(value)?,
+        "kb_res" => stats.kb_res = parse(value)?,
+        "invulnerable" => stats.invulnerable = parse(value)?,
+        _ => return Err(format!("CombatStats has no field {}", field)),
+    }
+    Ok(())
+}
+
+/// Lists the components out of the known ones that the entity has
+macro_rules! known_components {
+    ($entity:expr, $($component:ty),* $(,)?) => {{
+        let mut names = vec![];
+        $(
+            if $entity.has::<$component>() {
+                names.push(stringify!($component));
+            }
+        )*
+        names
+    }};
+}
+
+/// Names of the components of the entity, only types known to the devtools are listed
+pub fn component_names(entity: &EntityRef) -> Vec<&'static str> {
+    use super::super::agent::controller::{DashAbility, HorizontalLock};
+    use super::super::agent::profile::ProfileKey;
+    use super::super::ai::AiControlled;
+    use super::super::inbox::Inbox;
+    use crate::effect::afterimage::AfterimageTrail;
+    use crate::effect::EffectData;
+    use crate::gfx::{Animation, Sprite};
+    use crate::particle::Emitter;
+    use crate::phx::{Gravity, Hitbox, OnGround, OnWall};
+    use crate::util::transition_log::TransitionLog;
+
+    known_components!(
+        entity,
+        Position,
+        Velocity,
+        Gravity,
+        ProfileKey,
+        OnGround,
+        OnWall,
+        Hitbox,
+        CombatStats,
+        PlayerControlledV2,
+        AiControlled,
+        HorizontalLock,
+        DashAbility,
+        Inbox,
+        Sprite,
+        Animation,
+        AfterimageTrail,
+        Emitter,
+        EffectData,
+        TransitionLog,
+    )
+}
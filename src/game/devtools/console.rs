@@ -0,0 +1,161 @@
+/*!
+Drop-down text console, opened with the backquote key.
+Commands are run by `commands::run`, log records are shown along with their output.
+*/
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use log::{Level, Log, Metadata, Record, SetLoggerError};
+use macroquad::input::{get_char_pressed, is_key_pressed, KeyCode};
+
+const SCROLLBACK_SIZE: usize = 256;
+
+/// Log records waiting to be moved into the scrollback, shared with the logger
+#[derive(Clone, Default)]
+pub struct LogBuffer(Arc<Mutex<Vec<String>>>);
+
+impl LogBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn push(&self, line: String) {
+        if let Ok(mut lines) = self.0.lock() {
+            lines.push(line);
+        }
+    }
+    fn take(&self) -> Vec<String> {
+        self.0.lock().map(|mut lines| std::mem::take(&mut *lines)).unwrap_or_default()
+    }
+}
+
+/// Passes records on to another logger, and copies the ones at info level or above to the console
+pub struct ConsoleLogger<L> {
+    inner: L,
+    buffer: LogBuffer,
+}
+
+impl<L: Log + 'static> ConsoleLogger<L> {
+    pub fn new(inner: L, buffer: LogBuffer) -> Self {
+        Self { inner, buffer }
+    }
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        log::set_max_level(log::LevelFilter::Trace);
+        log::set_boxed_logger(Box::new(self))
+    }
+}
+
+impl<L: Log> Log for ConsoleLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+    fn log(&self, record: &Record) {
+        self.inner.log(record);
+        if record.level() <= Level::Info && self.enabled(record.metadata()) {
+            self.buffer.push(format!("{} {}", record.level(), record.args()));
+        }
+    }
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+pub struct Console {
+    pub open: bool,
+    input: String,
+    /// submitted lines, oldest first
+    history: Vec<String>,
+    /// position in `history` while browsing it with the arrow keys
+    history_index: Option<usize>,
+    scrollback: VecDeque<String>,
+    log: LogBuffer,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            history: vec![],
+            history_index: None,
+            scrollback: VecDeque::with_capacity(SCROLLBACK_SIZE),
+            log: LogBuffer::new(),
+        }
+    }
+
+    /// Log records pushed into the buffer show up in the scrollback
+    pub fn attach_log(&mut self, log: LogBuffer) {
+        self.log = log;
+    }
+
+    pub fn print(&mut self, line: impl Into<String>) {
+        if self.scrollback.len() == SCROLLBACK_SIZE {
+            self.scrollback.pop_front();
+        }
+        self.scrollback.push_back(line.into());
+    }
+
+    /// Lines of output, newest last
+    pub fn scrollback(&self) -> impl DoubleEndedIterator<Item = &String> {
+        self.scrollback.iter()
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Moves log records to the scrollback and handles typing,
+    /// returns the line to run once it's submitted.
+    pub fn update(&mut self) -> Option<String> {
+        for line in self.log.take() {
+            self.print(line);
+        }
+
+        if is_key_pressed(KeyCode::GraveAccent) {
+            self.open = !self.open;
+        }
+        if !self.open {
+            // characters typed while closed aren't meant for the console
+            while get_char_pressed().is_some() {}
+            return None;
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.open = false;
+            return None;
+        }
+
+        while let Some(character) = get_char_pressed() {
+            if !character.is_control() && character != '`' {
+                self.input.push(character);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.browse_history(true);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.browse_history(false);
+        }
+
+        if is_key_pressed(KeyCode::Enter) && !self.input.trim().is_empty() {
+            let line = std::mem::take(&mut self.input);
+            self.print(format!("> {}", line));
+            self.history.push(line.clone());
+            self.history_index = None;
+            return Some(line);
+        }
+        None
+    }
+
+    fn browse_history(&mut self, older: bool) {
+        let index = match (self.history_index, older) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) => Some(index + 1).filter(|&index| index < self.history.len()),
+        };
+        self.history_index = index;
+        self.input = index.map(|index| self.history[index].clone()).unwrap_or_default();
+    }
+}
@@ -0,0 +1,125 @@
+/*!
+Reloads assets once their files change, without restarting the game.
+
+Textures and maps are matched to their files through the asset manifest. A changed start map
+rebuilds the tile colliders and the current room, changed animation definitions are merged into
+the loaded ones.
+*/
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use macroquad::texture::{FilterMode, Texture2D};
+
+use super::Game;
+use crate::assets::manifest::Manifest;
+use crate::gfx::animation::{load_animations, Animation};
+use crate::util::file_watcher::{DirectoryWatcher, FileWatcher};
+
+pub const MEDIA_DIR: &str = "media";
+const SCAN_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct HotReload {
+    media: DirectoryWatcher,
+    /// files of animation definitions listed in the manifest
+    animations: Vec<(String, FileWatcher)>,
+}
+
+impl HotReload {
+    pub fn new(manifest: &Manifest) -> Self {
+        Self {
+            media: DirectoryWatcher::new(MEDIA_DIR, SCAN_INTERVAL),
+            animations: manifest
+                .animations
+                .iter()
+                .map(|path| (path.clone(), FileWatcher::new(path)))
+                .collect(),
+        }
+    }
+}
+
+/// Does nothing until the game is initialized
+pub fn update(game: &mut Game) {
+    let hot_reload = match game.hot_reload.as_mut() {
+        Some(hot_reload) => hot_reload,
+        None => return,
+    };
+    let changed_media = hot_reload.media.changed();
+    let changed_animations: Vec<_> = hot_reload
+        .animations
+        .iter_mut()
+        .filter_map(|(path, watcher)| watcher.changed().then(|| path.clone()))
+        .collect();
+
+    let mut map_changed = false;
+    for path in changed_media {
+        let manifest = &game.assets.manifest;
+        let start_map = manifest.maps.get(&manifest.start_map);
+        if start_map.map_or(false, |def| Path::new(&def.path) == path) {
+            map_changed = true;
+        } else {
+            reload_texture(game, &path);
+        }
+    }
+    if map_changed {
+        match game.reload_map() {
+            Ok(()) => log::info!("Reloaded the map"),
+            Err(err) => log::warn!("Keeping the current map, {}", err),
+        }
+    }
+
+    for path in changed_animations {
+        reload_animations(game, &path);
+    }
+}
+
+/// Only files of textures listed in the manifest are loaded
+fn reload_texture(game: &mut Game, path: &Path) {
+    let id = match game.assets.texture_by_path(path) {
+        Some(id) => id,
+        None => return,
+    };
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            log::warn!("Can't read {}, {}", path.display(), err);
+            return;
+        }
+    };
+    // macroquad's decoder panics on broken files, which are common while one is still being written
+    let texture = match decode_texture(&bytes) {
+        Ok(texture) => texture,
+        Err(err) => {
+            log::warn!("Can't decode {}, keeping the current texture, {}", path.display(), err);
+            return;
+        }
+    };
+    texture.set_filter(FilterMode::Nearest);
+    if let Some(old) = game.assets.replace_texture(id, texture) {
+        old.delete();
+    }
+    log::info!("Reloaded {}", path.display());
+}
+
+fn decode_texture(bytes: &[u8]) -> Result<Texture2D, String> {
+    let image = image::load_from_memory(bytes).map_err(|err| err.to_string())?.to_rgba8();
+    let (width, height) = image.dimensions();
+    match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(width), Ok(height)) => Ok(Texture2D::from_rgba8(width, height, &image)),
+        _ => Err(format!("{}x{} is too large", width, height)),
+    }
+}
+
+/// Animations being played start over, so they pick up their new frames
+fn reload_animations(game: &mut Game, path: &str) {
+    match load_animations(path) {
+        Ok(animations) => {
+            game.resources.animations.extend(animations);
+            for (_, animation) in game.world.query_mut::<&mut Animation>() {
+                animation.restart();
+            }
+            log::info!("Reloaded {}", path);
+        }
+        Err(err) => log::warn!("Keeping the current animations, {}", err),
+    }
+}
@@ -0,0 +1,214 @@
+/*!
+Entity inspector, F4 toggles it.
+While it's open clicking a sprite or a collider selects its entity, the panel shows the components
+of the selection and lets their numeric fields be edited while the game runs.
+*/
+use glam::Vec2;
+use hecs::{Entity, World};
+use macroquad::hash;
+use macroquad::input::{
+    is_key_pressed, is_mouse_button_pressed, mouse_position, KeyCode, MouseButton,
+};
+use macroquad::math::vec2;
+use macroquad::ui::{root_ui, widgets, Ui};
+use macroquad::window::screen_width;
+use resphys::ColliderState;
+
+use super::super::combat::CombatStats;
+use super::super::resources::Resources;
+use super::commands::component_names;
+use super::{cursor_position, Game};
+use crate::gfx::{Animation, Sprite};
+use crate::phx::{Gravity, Hitbox, OnGround, Position, Velocity};
+use crate::util::transition_log::TransitionLog;
+
+const PANEL_WIDTH: f32 = 320.;
+const PANEL_HEIGHT: f32 = 480.;
+/// leaves room for the tick counter above
+const PANEL_TOP: f32 = 32.;
+const MARGIN: f32 = 8.;
+
+pub struct Inspector {
+    pub open: bool,
+    pub selected: Option<Entity>,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Self { open: false, selected: None }
+    }
+}
+
+/// Toggles the inspector and picks the entity under the cursor on click
+pub fn handle_input(game: &mut Game) {
+    if game.console.open {
+        return;
+    }
+    if is_key_pressed(KeyCode::F4) {
+        game.inspector.open = !game.inspector.open;
+    }
+    if !game.inspector.open || !is_mouse_button_pressed(MouseButton::Left) {
+        return;
+    }
+    let (x, y) = mouse_position();
+    // clicks on the panel itself are for the panel
+    if root_ui().is_mouse_over(vec2(x, y)) {
+        return;
+    }
+    game.inspector.selected = pick(&game.world, &game.resources, cursor_position(game));
+}
+
+/// Entity whose sprite is at the point, or else the one owning a collider there
+pub fn pick(world: &World, resources: &Resources, point: Vec2) -> Option<Entity> {
+    let sprite_entity = world
+        .query::<(&Position, &Sprite)>()
+        .iter()
+        .filter(|(_, (_, sprite))| sprite.visible)
+        .find(|(_, (position, sprite))| {
+            sprite.bounds(position.src).contains(vec2(point.x, point.y))
+        })
+        .map(|(entity, _)| entity);
+
+    sprite_entity.or_else(|| {
+        resources.phys_colliders.iter().find_map(|(_, collider)| {
+            let body = &resources.phys_bodies[collider.owner];
+            let center = body.position + collider.offset;
+            let inside = (point - center).abs().cmple(collider.shape.half_exts).all();
+            if inside {
+                resources.body_entity_map.get(&collider.owner).copied()
+            } else {
+                None
+            }
+        })
+    })
+}
+
+/// Draws the panel of the selected entity, edits go straight into its components
+pub fn panel(game: &mut Game) {
+    let inspector = &mut game.inspector;
+    if !inspector.open {
+        return;
+    }
+    // selection might have been despawned since
+    if let Some(entity) = inspector.selected {
+        if !game.world.contains(entity) {
+            inspector.selected = None;
+        }
+    }
+
+    let world = &game.world;
+    let resources = &game.resources;
+    let selected = inspector.selected;
+    widgets::Window::new(
+        hash!(),
+        vec2(screen_width() - PANEL_WIDTH - MARGIN, PANEL_TOP),
+        vec2(PANEL_WIDTH, PANEL_HEIGHT),
+    )
+    .label("Inspector")
+    .ui(&mut *root_ui(), |ui| match selected {
+        Some(entity) => entity_ui(ui, world, resources, entity),
+        None => ui.label(None, "click a sprite or a collider"),
+    });
+}
+
+fn entity_ui(ui: &mut Ui, world: &World, resources: &Resources, entity: Entity) {
+    ui.label(None, &format!("entity {}", entity.id()));
+    if let Ok(entity_ref) = world.entity(entity) {
+        for name in component_names(&entity_ref) {
+            ui.label(None, &format!("  {}", name));
+        }
+    }
+
+    if let Ok(mut position) = world.get_mut::<Position>(entity) {
+        ui.separator();
+        ui.label(None, "Position");
+        drag_vec2(ui, hash!(), "src", &mut position.src);
+    }
+    if let Ok(mut velocity) = world.get_mut::<Velocity>(entity) {
+        ui.separator();
+        ui.label(None, "Velocity");
+        drag_vec2(ui, hash!(), "src", &mut velocity.src);
+    }
+    if let Ok(mut gravity) = world.get_mut::<Gravity>(entity) {
+        ui.separator();
+        ui.label(None, "Gravity");
+        ui.checkbox(hash!(), "enabled", &mut gravity.enabled);
+        drag_vec2(ui, hash!(), "strength", &mut gravity.strength);
+        ui.drag(hash!(), "fall_multiplier", None, &mut gravity.fall_multiplier);
+        ui.drag(hash!(), "terminal_velocity", None, &mut gravity.terminal_velocity);
+        ui.drag(hash!(), "apex_hang_time", None, &mut gravity.apex_hang_time);
+    }
+    if let Ok(ground) = world.get::<OnGround>(entity) {
+        ui.separator();
+        ui.label(None, "OnGround");
+        ui.label(None, &format!("on_ground {}", ground.on_ground));
+        ui.label(None, &format!("sensor {:?}", ground.sensor_handle));
+    }
+    if let Ok(mut stats) = world.get_mut::<CombatStats>(entity) {
+        ui.separator();
+        ui.label(None, "CombatStats");
+        drag_vec2(ui, hash!(), "kb_force", &mut stats.kb_force);
+        ui.drag(hash!(), "kb_res", None, &mut stats.kb_res);
+        ui.checkbox(hash!(), "invulnerable", &mut stats.invulnerable);
+    }
+    if let Ok(animation) = world.get::<Animation>(entity) {
+        ui.separator();
+        ui.label(None, "Animation");
+        ui.label(None, &format!("{} frame {}", animation.played, animation.frame));
+        ui.label(None, &format!("acc {:.3}  repeat {}", animation.acc, animation.repeat));
+    }
+    if let Ok(log) = world.get::<TransitionLog>(entity) {
+        ui.separator();
+        ui.label(None, "State machine");
+        ui.label(None, &log.active);
+        if let Some(record) = log.recent().next() {
+            let line =
+                format!("{} {} -> {} #{}", record.tick, record.from, record.to, record.transition);
+            ui.label(None, &line);
+            if let Some(label) = &record.label {
+                ui.label(None, &format!("  {}", label));
+            }
+        }
+    }
+    if let Ok(hitbox) = world.get::<Hitbox>(entity) {
+        ui.separator();
+        physics_ui(ui, resources, &hitbox);
+    }
+}
+
+/// The body behind the hitbox, every collider attached to it and its entry in `BodyEntityMap`
+fn physics_ui(ui: &mut Ui, resources: &Resources, hitbox: &Hitbox) {
+    let bhandle = resources.phys_colliders[hitbox.src].owner;
+    let body = &resources.phys_bodies[bhandle];
+    ui.label(None, &format!("Hitbox {:?}", hitbox.src));
+    ui.label(None, &format!("body {:?}", bhandle));
+    ui.label(None, &format!("  position {:.2} {:.2}", body.position.x, body.position.y));
+    ui.label(None, &format!("  velocity {:.2} {:.2}", body.velocity.x, body.velocity.y));
+    let mapped = match resources.body_entity_map.get(&bhandle) {
+        Some(entity) => format!("  mapped to entity {}", entity.id()),
+        None => "  missing from BodyEntityMap".to_owned(),
+    };
+    ui.label(None, &mapped);
+
+    for (chandle, collider) in resources.phys_colliders.iter() {
+        if collider.owner != bhandle {
+            continue;
+        }
+        let state = match collider.state {
+            ColliderState::Solid => "solid",
+            ColliderState::Sensor => "sensor",
+        };
+        let exts = collider.shape.half_exts;
+        let offset = collider.offset;
+        ui.label(None, &format!("collider {:?} {}", chandle, state));
+        ui.label(
+            None,
+            &format!("  half_exts {} {}  offset {} {}", exts.x, exts.y, offset.x, offset.y),
+        );
+    }
+}
+
+fn drag_vec2(ui: &mut Ui, id: u64, label: &str, value: &mut Vec2) {
+    ui.drag(hash!(id, "x"), &format!("{} x", label), None, &mut value.x);
+    ui.drag(hash!(id, "y"), &format!("{} y", label), None, &mut value.y);
+}
@@ -0,0 +1,132 @@
+pub mod commands;
+pub mod console;
+pub mod hot_reload;
+pub mod inspector;
+
+use std::fs;
+use std::path::Path;
+
+use glam::Vec2;
+use hecs::World;
+use macroquad::input::{is_key_pressed, mouse_position, KeyCode};
+
+use super::agent::controller::PlayerControlledV2;
+use super::ai::AiControlled;
+use super::Game;
+use crate::util::profiler::Capture;
+use crate::util::FrameTimer;
+
+pub const GRAPHS_DIR: &str = "graphs";
+pub const CAPTURES_DIR: &str = "captures";
+
+/// Debug drawings that can be turned off from the console
+pub struct Overlays {
+    pub colliders: bool,
+    pub hurtboxes: bool,
+    pub state_machines: bool,
+}
+
+impl Overlays {
+    pub fn new() -> Self {
+        Self { colliders: true, hurtboxes: true, state_machines: true }
+    }
+}
+
+/// Reloads changed assets, handles the console, the inspector and the time controls,
+/// returns true if a single update was requested.
+/// Game input and the time control keys are ignored while the console is open.
+pub fn begin_frame(game: &mut Game, timer: &mut FrameTimer) -> bool {
+    hot_reload::update(game);
+
+    let was_open = game.console.open;
+    if let Some(line) = game.console.update() {
+        if let Err(err) = commands::run(game, &line) {
+            game.console.print(err);
+        }
+    }
+    // the key closing the console would be picked up otherwise
+    if !was_open && !game.console.open {
+        listen_for_binding(game);
+    }
+    game.resources.input_buttons.blocked = game.console.open;
+    inspector::handle_input(game);
+
+    let single_step = !game.console.open && game.time_control.handle_keys();
+    timer.set_time_scale(game.time_control.time_scale());
+    single_step
+}
+
+/// Binds the button waiting for a new input to the first one pressed
+fn listen_for_binding(game: &mut Game) {
+    let button = match game.rebinding {
+        Some(button) => button,
+        None => return,
+    };
+    let buttons = &mut game.resources.input_buttons;
+    if let Some(input) = buttons.listen() {
+        buttons.rebind(button, input);
+        game.rebinding = None;
+        log::info!("{:?} bound to {:?}", button, input);
+    }
+}
+
+/// Position of the mouse cursor in the world
+fn cursor_position(game: &Game) -> Vec2 {
+    let (x, y) = mouse_position();
+    let position = game.resources.camera.src().screen_to_world(macroquad::math::vec2(x, y));
+    Vec2::new(position.x, position.y)
+}
+
+/// Shows the inspector panel and closes the profiled frame, F3 toggles the profiler overlay,
+/// F5 exports the state machine graphs and F6 starts or stops a capture
+pub fn end_frame(game: &mut Game) {
+    inspector::panel(game);
+
+    let colliders = game.resources.phys_colliders.iter().count() as u32;
+    game.profiler.end_frame(game.world.len(), colliders);
+
+    if is_key_pressed(KeyCode::F3) {
+        game.profiler.visible = !game.profiler.visible;
+    }
+    if is_key_pressed(KeyCode::F5) {
+        export_graphs(&game.world);
+    }
+    if is_key_pressed(KeyCode::F6) {
+        match game.profiler.stop_capture() {
+            Some(capture) => save_capture(&capture),
+            None => {
+                game.profiler.start_capture();
+                log::info!("Capture started, F6 to stop it");
+            }
+        }
+    }
+}
+
+pub fn save_capture(capture: &Capture) {
+    write_file(CAPTURES_DIR, "capture.csv", &capture.to_csv());
+    write_file(CAPTURES_DIR, "capture.json", &capture.to_chrome_trace());
+}
+
+/// Writes the state machine graphs of the player and enemies to DOT files.
+/// Enemies share a single graph, so only the first one found is written
+pub fn export_graphs(world: &World) {
+    let player = world.query::<&PlayerControlledV2>().iter().next().map(|(_, pc)| pc.to_dot());
+    let enemy = world.query::<&AiControlled>().iter().next().map(|(_, ai)| ai.to_dot());
+
+    let graphs = [("player", player), ("enemy", enemy)];
+    for (name, dot) in graphs.into_iter().filter_map(|(name, dot)| Some((name, dot?))) {
+        write_file(GRAPHS_DIR, &format!("{}.dot", name), &dot);
+    }
+}
+
+fn write_file(dir: &str, name: &str, contents: &str) {
+    if let Err(err) = fs::create_dir_all(dir) {
+        log::warn!("Can't create {}, {}", dir, err);
+        return;
+    }
+    let path = Path::new(dir).join(name);
+    match fs::write(&path, contents) {
+        Ok(()) => log::info!("Wrote {}", path.display()),
+        Err(err) => log::warn!("Can't write {}, {}", path.display(), err),
+    }
+}
@@ -0,0 +1,67 @@
+use hecs::{Entity, World};
+
+use super::resources::Resources;
+use crate::phx::LandedEvent;
+use crate::util::events::{EventBus, EventReader};
+
+/// Things that happened to an entity which its state machine might want to react to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AgentEvent {
+    /// got damaged by an attack
+    Hit,
+    /// touched the ground after being in the air
+    Landed,
+}
+
+/// Component, collects events sent to the entity.
+/// Events sent during a frame become visible once `inbox_delivery_system` runs and stay for one update.
+#[derive(Debug, Default)]
+pub struct Inbox {
+    received: Vec<AgentEvent>,
+    pending: Vec<AgentEvent>,
+}
+
+impl Inbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn send(&mut self, event: AgentEvent) {
+        self.pending.push(event);
+    }
+    pub fn received(&self, event: AgentEvent) -> bool {
+        self.received.contains(&event)
+    }
+    fn deliver(&mut self) {
+        self.received.clear();
+        std::mem::swap(&mut self.received, &mut self.pending);
+    }
+}
+
+/// Forwards `LandedEvent`s to the inboxes of the entities that landed
+pub fn landed_inbox_system(
+    world: &mut World,
+    events: &EventBus,
+    reader: &mut EventReader<LandedEvent>,
+) {
+    for LandedEvent { entity } in events.read(reader) {
+        if let Ok(mut inbox) = world.get_mut::<Inbox>(*entity) {
+            inbox.send(AgentEvent::Landed);
+        }
+    }
+}
+
+/// Should run right before state machines are updated.
+pub fn inbox_delivery_system(world: &mut World) {
+    for (_eid, inbox) in world.query_mut::<&mut Inbox>() {
+        inbox.deliver();
+    }
+}
+
+/// Transition condition met when the entity received the event since its last update
+pub fn received(
+    event: AgentEvent,
+) -> impl Fn(Entity, &World, &Resources) -> bool + 'static + Send + Sync {
+    move |entity: Entity, world: &World, _resources: &Resources| {
+        world.get::<Inbox>(entity).map_or(false, |inbox| inbox.received(event))
+    }
+}
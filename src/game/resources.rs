@@ -1,11 +1,16 @@
 use crate::{
-    game::combat::{DamageQueue, HurtQueue},
+    game::agent::profile::MovementProfiles,
     gfx::AnimationStorage,
-    phx::{BodyEntityMap, BodySet, ColliderSet, PhysicsWorld},
+    particle::Particles,
+    phx::{BodyEntityMap, BodySet, ColliderSet, LandedEvent, PhysicsWorld},
+    util::events::{EventBus, EventReader},
     util::{ButtonsState, Camera},
 };
 
-use super::stage::Stage;
+#[cfg(feature = "devtools")]
+use crate::util::file_watcher::FileWatcher;
+
+use super::stage::{RoomEnteredEvent, Stage};
 
 pub struct Resources {
     pub animations: AnimationStorage,
@@ -14,10 +19,20 @@ pub struct Resources {
     pub phys_bodies: BodySet,
     pub phys_colliders: ColliderSet,
     pub input_buttons: ButtonsState,
-    pub hurt_queue: HurtQueue,
-    pub damage_queue: DamageQueue,
+    pub events: EventBus,
     pub body_entity_map: BodyEntityMap,
     pub stage: Stage,
+    pub particles: Particles,
+    pub movement: MovementProfiles,
+    pub landing_dust_reader: EventReader<LandedEvent>,
+    pub landing_squash_reader: EventReader<LandedEvent>,
+    pub dash_landing_reader: EventReader<LandedEvent>,
+    pub inbox_landing_reader: EventReader<LandedEvent>,
+    pub room_entered_reader: EventReader<RoomEnteredEvent>,
+    /// number of updates executed so far
+    pub tick: u64,
+    #[cfg(feature = "devtools")]
+    pub player_fsm_watcher: FileWatcher,
 }
 
 impl Default for Resources {
@@ -34,11 +49,11 @@ impl Resources {
         let phys_bodies = BodySet::new();
         let phys_colliders = ColliderSet::new();
         let input_buttons = ButtonsState::new();
-        // TODO: Replace HurtQueue and DamageQueue with an event system.
-        let hurt_queue = HurtQueue::new();
-        let damage_queue = DamageQueue::new();
+        let events = EventBus::new();
         let body_entity_map = BodyEntityMap::default();
-        let stage = Stage::from_ldtk();
+        let stage = Stage::new();
+        let particles = Particles::new();
+        let movement = MovementProfiles::load();
         Self {
             animations,
             camera,
@@ -46,10 +61,19 @@ impl Resources {
             phys_bodies,
             phys_colliders,
             input_buttons,
-            hurt_queue,
-            damage_queue,
+            events,
             body_entity_map,
             stage,
+            particles,
+            movement,
+            landing_dust_reader: EventReader::new(),
+            landing_squash_reader: EventReader::new(),
+            dash_landing_reader: EventReader::new(),
+            inbox_landing_reader: EventReader::new(),
+            room_entered_reader: EventReader::new(),
+            tick: 0,
+            #[cfg(feature = "devtools")]
+            player_fsm_watcher: FileWatcher::new(crate::game::agent::controller::PLAYER_FSM_PATH),
         }
     }
 }
@@ -0,0 +1,343 @@
+/*!
+Systems registered by name into stages, instead of a hand-written list of calls.
+
+Stages run in order, the shared `CommandBuffer` is flushed after each one.
+The event bus is told about every tick and system, so it can expire events after a tick.
+Within a stage systems run in the order they were added, unless moved by `after`/`before`.
+*/
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use enum_map::{Enum, EnumMap};
+use hecs::{CommandBuffer, World};
+
+use super::resources::Resources;
+
+pub type SystemFn = fn(&mut World, &mut Resources, &mut CommandBuffer);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Enum)]
+pub enum SystemStage {
+    Input,
+    Logic,
+    Physics,
+    PostPhysics,
+    Effects,
+}
+
+pub struct System {
+    pub name: &'static str,
+    pub stage: SystemStage,
+    run: SystemFn,
+    /// disabled systems are skipped, toggled from devtools
+    pub enabled: bool,
+    /// when the last run started and how long it took
+    pub started: Instant,
+    pub time: Duration,
+}
+
+pub struct Schedule {
+    /// in order of execution
+    systems: Vec<System>,
+}
+
+impl Schedule {
+    pub fn execute(&mut self, world: &mut World, resources: &mut Resources) {
+        let mut cmd = CommandBuffer::new();
+        let mut systems = self.systems.iter_mut().enumerate().peekable();
+
+        resources.events.begin_tick();
+        while let Some((position, system)) = systems.next() {
+            resources.events.begin_system(position);
+            if system.enabled {
+                system.started = Instant::now();
+                (system.run)(world, resources, &mut cmd);
+                system.time = system.started.elapsed();
+            } else {
+                system.time = Duration::ZERO;
+            }
+
+            // entities are created and removed at stage boundaries
+            if systems.peek().map_or(true, |(_, next)| next.stage != system.stage) {
+                cmd.run_on(world);
+            }
+        }
+        resources.events.end_tick();
+    }
+
+    pub fn systems(&self) -> impl Iterator<Item = &System> {
+        self.systems.iter()
+    }
+
+    /// Returns false if there's no system with this name
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.systems.iter_mut().find(|system| system.name == name) {
+            Some(system) => {
+                system.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ScheduleError {
+    /// the same name was used for two systems
+    DuplicateSystem(&'static str),
+    /// ordering constraint refers to a system that isn't registered
+    UnknownSystem { system: &'static str, constraint: &'static str },
+    /// ordering constraint between systems of different stages contradicts the stage order
+    CrossStage { system: &'static str, constraint: &'static str },
+    /// ordering constraints of these systems form a cycle
+    Cycle(Vec<&'static str>),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::DuplicateSystem(name) => write!(f, "system {} added twice", name),
+            ScheduleError::UnknownSystem { system, constraint } => {
+                write!(f, "system {} is ordered against unknown system {}", system, constraint)
+            }
+            ScheduleError::CrossStage { system, constraint } => {
+                write!(f, "ordering of {} against {} contradicts their stages", system, constraint)
+            }
+            ScheduleError::Cycle(names) => {
+                write!(f, "ordering of systems {} is cyclic", names.join(", "))
+            }
+        }
+    }
+}
+
+struct SystemDesc {
+    name: &'static str,
+    stage: SystemStage,
+    run: SystemFn,
+    after: Vec<&'static str>,
+    before: Vec<&'static str>,
+}
+
+pub struct ScheduleBuilder {
+    systems: Vec<SystemDesc>,
+}
+
+impl ScheduleBuilder {
+    pub fn new() -> Self {
+        Self { systems: vec![] }
+    }
+
+    pub fn add_system(mut self, stage: SystemStage, name: &'static str, run: SystemFn) -> Self {
+        self.systems.push(SystemDesc { name, stage, run, after: vec![], before: vec![] });
+        self
+    }
+    /// The most recently added system runs after the named one
+    pub fn after(mut self, name: &'static str) -> Self {
+        if let Some(system) = self.systems.last_mut() {
+            system.after.push(name);
+        }
+        self
+    }
+    /// The most recently added system runs before the named one
+    pub fn before(mut self, name: &'static str) -> Self {
+        if let Some(system) = self.systems.last_mut() {
+            system.before.push(name);
+        }
+        self
+    }
+
+    /// Orders the systems, every problem found is reported.
+    pub fn build(self) -> Result<Schedule, Vec<ScheduleError>> {
+        let mut errors = vec![];
+
+        let mut indices = HashMap::new();
+        for (index, system) in self.systems.iter().enumerate() {
+            if indices.insert(system.name, index).is_some() {
+                errors.push(ScheduleError::DuplicateSystem(system.name));
+            }
+        }
+
+        // edges go from the system that runs first, only within a stage
+        let mut successors = vec![vec![]; self.systems.len()];
+        let mut predecessors = vec![0; self.systems.len()];
+        for (index, system) in self.systems.iter().enumerate() {
+            let constraints = system.after.iter().map(|&name| (name, true));
+            let constraints = constraints.chain(system.before.iter().map(|&name| (name, false)));
+            for (constraint, runs_after) in constraints {
+                let other = match indices.get(constraint) {
+                    Some(&other) => other,
+                    None => {
+                        errors
+                            .push(ScheduleError::UnknownSystem { system: system.name, constraint });
+                        continue;
+                    }
+                };
+                let (first, then) = if runs_after { (other, index) } else { (index, other) };
+
+                let first_stage = self.systems[first].stage.into_usize();
+                let then_stage = self.systems[then].stage.into_usize();
+                if first_stage > then_stage {
+                    errors.push(ScheduleError::CrossStage { system: system.name, constraint });
+                } else if first_stage == then_stage {
+                    successors[first].push(then);
+                    predecessors[then] += 1;
+                }
+            }
+        }
+
+        // stages in order, within one the earliest added system that's ready goes first
+        let mut order = Vec::with_capacity(self.systems.len());
+        let mut stages: EnumMap<SystemStage, Vec<usize>> = EnumMap::default();
+        for (index, system) in self.systems.iter().enumerate() {
+            stages[system.stage].push(index);
+        }
+        for (_, mut pending) in stages {
+            while let Some(position) = pending.iter().position(|&index| predecessors[index] == 0) {
+                let index = pending.remove(position);
+                for &next in successors[index].iter() {
+                    predecessors[next] -= 1;
+                }
+                order.push(index);
+            }
+            if !pending.is_empty() {
+                errors.push(ScheduleError::Cycle(
+                    pending.iter().map(|&index| self.systems[index].name).collect(),
+                ));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let mut systems: Vec<_> = self.systems.into_iter().map(Some).collect();
+        let systems = order
+            .into_iter()
+            .filter_map(|index| systems[index].take())
+            .map(|desc| System {
+                name: desc.name,
+                stage: desc.stage,
+                run: desc.run,
+                enabled: true,
+                started: Instant::now(),
+                time: Duration::ZERO,
+            })
+            .collect();
+
+        Ok(Schedule { systems })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SystemStage::*;
+    use super::*;
+
+    fn noop(_: &mut World, _: &mut Resources, _: &mut CommandBuffer) {}
+
+    fn names(schedule: &Schedule) -> Vec<&'static str> {
+        schedule.systems().map(|system| system.name).collect()
+    }
+
+    #[test]
+    fn keeps_the_order_systems_were_added_in() {
+        let schedule = ScheduleBuilder::new()
+            .add_system(Logic, "a", noop)
+            .add_system(Logic, "b", noop)
+            .add_system(Logic, "c", noop)
+            .build()
+            .unwrap();
+        assert_eq!(names(&schedule), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn runs_stages_in_order() {
+        let schedule = ScheduleBuilder::new()
+            .add_system(Effects, "effects", noop)
+            .add_system(Input, "input", noop)
+            .add_system(Physics, "physics", noop)
+            .build()
+            .unwrap();
+        assert_eq!(names(&schedule), ["input", "physics", "effects"]);
+    }
+
+    #[test]
+    fn after_and_before_reorder_within_a_stage() {
+        let schedule = ScheduleBuilder::new()
+            .add_system(Logic, "a", noop)
+            .after("c")
+            .add_system(Logic, "b", noop)
+            .add_system(Logic, "c", noop)
+            .add_system(Logic, "d", noop)
+            .before("b")
+            .build()
+            .unwrap();
+        assert_eq!(names(&schedule), ["c", "a", "d", "b"]);
+    }
+
+    #[test]
+    fn reports_cycles() {
+        let errors = ScheduleBuilder::new()
+            .add_system(Logic, "a", noop)
+            .after("b")
+            .add_system(Logic, "b", noop)
+            .after("a")
+            .add_system(Logic, "c", noop)
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(errors.as_slice(), [ScheduleError::Cycle(names)] if names == &["a", "b"]));
+    }
+
+    #[test]
+    fn reports_ordering_against_the_stage_order() {
+        let errors = ScheduleBuilder::new()
+            .add_system(Logic, "logic", noop)
+            .after("effects")
+            .add_system(Effects, "effects", noop)
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(
+            errors.as_slice(),
+            [ScheduleError::CrossStage { system: "logic", constraint: "effects" }]
+        ));
+    }
+
+    #[test]
+    fn allows_ordering_that_agrees_with_the_stage_order() {
+        let schedule = ScheduleBuilder::new()
+            .add_system(Effects, "effects", noop)
+            .after("logic")
+            .add_system(Logic, "logic", noop)
+            .build()
+            .unwrap();
+        assert_eq!(names(&schedule), ["logic", "effects"]);
+    }
+
+    #[test]
+    fn reports_unknown_and_duplicate_systems() {
+        let errors = ScheduleBuilder::new()
+            .add_system(Logic, "a", noop)
+            .before("missing")
+            .add_system(Logic, "a", noop)
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                ScheduleError::DuplicateSystem("a"),
+                ScheduleError::UnknownSystem { system: "a", constraint: "missing" },
+            ]
+        ));
+    }
+
+    #[test]
+    fn disables_systems_by_name() {
+        let mut schedule = ScheduleBuilder::new().add_system(Logic, "a", noop).build().unwrap();
+        assert!(schedule.set_enabled("a", false));
+        assert!(!schedule.systems().next().unwrap().enabled);
+        assert!(!schedule.set_enabled("missing", false));
+    }
+}
@@ -2,6 +2,19 @@ pub use self::room::Room;
 
 pub mod room;
 
+use hecs::{Entity, World};
+
+use crate::phx::Position;
+use crate::util::events::{EventBus, EventReader};
+use crate::GAME_DIMENSIONS;
+
+/// Published when the entity followed by the camera is placed in a room, which for now only
+/// happens when the map is loaded
+#[derive(Debug, Clone)]
+pub struct RoomEnteredEvent {
+    pub entity: Entity,
+}
+
 // TODO: Extend to support multiple rooms and traversal between them
 // TODO Hint: Traversal could be implemented by generating 4 collision shapes and monitoring collision with them.
 /// `Stage` is a collection of `Room`s defining how granular gameplay environments are linked together.
@@ -10,32 +23,39 @@ pub struct Stage {
 }
 
 impl Stage {
-    pub fn from_ldtk() -> Self {
-        use ldtk_rust::Project;
-
-        const BASE_DIR: &str = "media/tilemap/";
-        const TEST_MAP: &str = "test2.ldtk";
-        const MAGIC_ID: i64 = 25;
-
-        let project = Project::new(BASE_DIR.to_owned() + TEST_MAP);
-
-        let mut current_level_index: Option<usize> = None;
-
-        for (idx, level) in project.levels.iter().enumerate() {
-            if level.uid == MAGIC_ID {
-                current_level_index = Some(idx);
-                break;
-            }
-        }
-
-        let only_level = &project.levels
-            [current_level_index.unwrap_or_else(|| panic!("Level {} doesn't exist", MAGIC_ID))];
-
-        let room = Room::from_ldtk(only_level);
-
+    /// A single room the size of the screen, until the map is loaded
+    pub fn new() -> Self {
+        let room = Room {
+            top: 0.,
+            left: 0.,
+            right: GAME_DIMENSIONS.0 as f32,
+            bottom: GAME_DIMENSIONS.1 as f32,
+        };
         Self { room }
     }
+    pub fn set_room(&mut self, room: Room) {
+        self.room = room;
+    }
     pub fn current_room(&self) -> &Room {
         &self.room
     }
 }
+
+/// Logs who entered the room and where they are in it
+pub fn room_entered_system(
+    world: &World,
+    events: &EventBus,
+    reader: &mut EventReader<RoomEnteredEvent>,
+    room: &Room,
+) {
+    for RoomEnteredEvent { entity } in events.read(reader) {
+        let position = world.get::<Position>(*entity).map(|position| position.src);
+        log::info!(
+            "Entity {} entered the {}x{} room at {:?}",
+            entity.id(),
+            room.right - room.left,
+            room.bottom - room.top,
+            position.ok()
+        );
+    }
+}
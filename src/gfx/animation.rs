@@ -1,18 +1,63 @@
 use super::Sprite;
+use crate::assets::TextureId;
+use crate::util::config::{self, ConfigError};
 use crate::FRAMETIME;
 use hecs::World;
+use serde::Deserialize;
 
 use macroquad::math::Rect;
 
 pub struct AnimationTemplate {
     pub repeat: bool,
-    pub texture_name: String,
+    pub texture: TextureId,
     /// rect to be set on `Sprite` component
     pub rect: Rect,
     pub move_by: f32,
     pub frames: Vec<Frame>,
 }
 
+/// `AnimationTemplate` as written in the definitions file
+#[derive(Deserialize)]
+struct AnimationDef {
+    /// ID the asset manifest gives the texture
+    texture: String,
+    /// x, y, width and height of the first frame
+    rect: (f32, f32, f32, f32),
+    move_by: f32,
+    repeat: bool,
+    /// duration of every frame
+    frames: Vec<f32>,
+}
+
+/// Loads templates from a file of definitions keyed by animation name
+#[cfg(feature = "devtools")]
+pub fn load_animations(path: &str) -> Result<super::AnimationStorage, ConfigError> {
+    let text = std::fs::read_to_string(path).map_err(|err| ConfigError::Io(path.into(), err))?;
+    parse_animations(path, &text)
+}
+
+/// Templates out of definitions already read from the file, animations without frames are skipped
+pub fn parse_animations(path: &str, text: &str) -> Result<super::AnimationStorage, ConfigError> {
+    let defs: fxhash::FxHashMap<String, AnimationDef> = config::parse(path, text)?;
+    let mut storage = super::AnimationStorage::default();
+    for (name, def) in defs {
+        if def.frames.is_empty() {
+            log::warn!("Animation {} in {} has no frames", name, path);
+            continue;
+        }
+        let (x, y, w, h) = def.rect;
+        let template = AnimationTemplate {
+            repeat: def.repeat,
+            texture: TextureId::new(&def.texture),
+            rect: Rect::new(x, y, w, h),
+            move_by: def.move_by,
+            frames: def.frames.into_iter().map(|duration| Frame { duration }).collect(),
+        };
+        storage.insert(name, template);
+    }
+    Ok(storage)
+}
+
 // Start with default, most robust frame implementation and optimize for simple repeated offset later
 #[derive(Debug, Clone)]
 pub struct Frame {
@@ -41,14 +86,16 @@ enum State {
 }
 
 impl Animation {
+    /// A missing animation is reported once it starts playing
     pub fn new(animation_storage: &super::AnimationStorage, animation_name: &str) -> Self {
-        let animation_template = animation_storage.get(animation_name).expect(animation_name);
+        let animation_template = animation_storage.get(animation_name);
         Self {
             played: animation_name.to_owned(),
             frame: 0,
-            frame_duration: animation_template.frames[0].duration,
+            frame_duration: animation_template
+                .map_or(f32::INFINITY, |template| template.frames[0].duration),
             acc: 0.,
-            repeat: animation_template.repeat,
+            repeat: animation_template.map_or(false, |template| template.repeat),
             state: State::New,
         }
     }
@@ -56,30 +103,29 @@ impl Animation {
         animation_storage: &super::AnimationStorage,
         animation_name: &str,
     ) -> (Self, Sprite) {
-        let animation_template = animation_storage.get(animation_name).expect(animation_name);
-        (
-            Self {
-                played: animation_name.to_owned(),
-                frame: 0,
-                frame_duration: animation_template.frames[0].duration,
-                acc: 0.,
-                repeat: animation_template.repeat,
-                state: State::New,
-            },
-            Sprite::new(
-                animation_template.texture_name.clone(),
-                animation_template.rect.x,
-                animation_template.rect.y,
-                animation_template.rect.w,
-                animation_template.rect.h,
+        let sprite = match animation_storage.get(animation_name) {
+            Some(template) => Sprite::new(
+                template.texture,
+                template.rect.x,
+                template.rect.y,
+                template.rect.w,
+                template.rect.h,
             ),
-        )
+            None => Sprite::new(TextureId::PLACEHOLDER, 0., 0., 16., 16.),
+        };
+        (Self::new(animation_storage, animation_name), sprite)
     }
 
     pub fn change(&mut self, animation_name: &str) {
         self.played = animation_name.to_owned();
         self.state = State::New;
     }
+    /// Starts over with the current template, finished animations stay finished
+    pub fn restart(&mut self) {
+        if !matches!(self.state, State::Finished) {
+            self.state = State::New;
+        }
+    }
 }
 
 pub fn animate_system(world: &mut World, animation_storage: &super::AnimationStorage) {
@@ -97,9 +143,15 @@ fn animate(
 ) {
     match animation.state {
         State::New => {
-            let animation_template =
-                animation_storage.get(&animation.played).expect(&animation.played);
-            sprite.texture = animation_template.texture_name.clone();
+            let animation_template = match animation_storage.get(&animation.played) {
+                Some(template) => template,
+                None => {
+                    log::error!("Animation {} doesn't exist", animation.played);
+                    animation.state = State::Finished;
+                    return;
+                }
+            };
+            sprite.texture = animation_template.texture;
             sprite.rect = animation_template.rect;
 
             animation.frame = 0;
@@ -115,8 +167,13 @@ fn animate(
                 animation.acc -= animation.frame_duration;
                 animation.frame += 1;
 
-                let animation_template =
-                    animation_storage.get(&animation.played).expect(&animation.played);
+                let animation_template = match animation_storage.get(&animation.played) {
+                    Some(template) => template,
+                    None => {
+                        animation.state = State::Finished;
+                        return;
+                    }
+                };
                 if let Some(frame) = animation_template.frames.get(animation.frame) {
                     sprite.rect.x += animation_template.move_by;
                     animation.frame_duration = frame.duration;
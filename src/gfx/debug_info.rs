@@ -1,11 +1,22 @@
-use super::align2subpixels;
+use super::{align2subpixels, Sprite};
+use crate::game::combat::HurtInfo;
+use crate::game::devtools::console::Console;
 use crate::game::resources::Resources;
-use crate::phx::ColliderTag;
-use crate::GAME_SCALE;
+use crate::phx::{ColliderTag, Position};
+use crate::util::profiler::{Profiler, GRAPH_LENGTH};
+use crate::util::time_control::TimeControl;
+use crate::util::transition_log::TransitionLog;
+use crate::{FRAMETIME, GAME_SCALE};
 use glam::Vec2;
+use hecs::World;
+use ringbuffer::RingBufferExt;
 
-use macroquad::color::{GREEN, RED, YELLOW};
-use macroquad::shapes::draw_rectangle;
+use macroquad::camera::set_default_camera;
+use macroquad::color::{Color, BLUE, GREEN, ORANGE, RED, WHITE, YELLOW};
+use macroquad::math::Rect;
+use macroquad::shapes::{draw_line, draw_rectangle, draw_rectangle_lines};
+use macroquad::text::{draw_text, draw_text_ex, measure_text, TextParams};
+use macroquad::window::{screen_height, screen_width};
 use resphys::{Collider, ColliderState};
 
 pub fn visualize_colliders(resources: &Resources) {
@@ -33,11 +44,10 @@ fn draw_collider(collider: &Collider<ColliderTag>, position: Vec2) {
 }
 
 pub fn visualize_boxes(resources: &Resources) {
-    let hurt_queue = &resources.hurt_queue;
     let mut color = RED;
     color.a = 0.6;
 
-    for hurt_info in hurt_queue.copy_msgs.iter() {
+    for hurt_info in resources.events.published::<HurtInfo>() {
         let actual_pos = hurt_info.position - hurt_info.half_exts;
         draw_rectangle(
             align2subpixels(actual_pos.x, crate::GAME_SCALE as f32),
@@ -48,3 +58,160 @@ pub fn visualize_boxes(resources: &Resources) {
         );
     }
 }
+
+/// Draws the active states and the most recent transitions above entities that log them
+pub fn visualize_state_machines(world: &World) {
+    const SHOWN_TRANSITIONS: usize = 3;
+    const LINE_HEIGHT: f32 = 5.;
+
+    let faded = Color::new(1., 1., 1., 0.6);
+
+    for (_eid, (position, log)) in world.query::<(&Position, &TransitionLog)>().iter() {
+        let x = position.src.x - 8.;
+        let mut y = position.src.y - 12.;
+
+        for record in log.recent().take(SHOWN_TRANSITIONS) {
+            let line =
+                format!("{} {} -> {} #{}", record.tick, record.from, record.to, record.transition);
+            draw_debug_text(&line, x, y, faded);
+            y -= LINE_HEIGHT;
+        }
+        draw_debug_text(&log.active, x, y, WHITE);
+    }
+}
+
+/// Outlines the sprite of the entity picked in the inspector
+pub fn visualize_selection(world: &World, selected: Option<hecs::Entity>) {
+    let entity = match selected {
+        Some(entity) => entity,
+        None => return,
+    };
+    if let Ok(mut query) = world.query_one::<(&Position, Option<&Sprite>)>(entity) {
+        if let Some((position, sprite)) = query.get() {
+            let bounds = match sprite {
+                Some(sprite) => sprite.bounds(position.src),
+                None => Rect::new(position.src.x - 4., position.src.y - 4., 8., 8.),
+            };
+            draw_rectangle_lines(bounds.x, bounds.y, bounds.w, bounds.h, 0.5, ORANGE);
+        }
+    }
+}
+
+fn draw_debug_text(text: &str, x: f32, y: f32, color: Color) {
+    // text is rendered in game pixels, so it's scaled down to stay readable
+    draw_text_ex(
+        text,
+        align2subpixels(x, GAME_SCALE as f32),
+        align2subpixels(y, GAME_SCALE as f32),
+        TextParams {
+            font_size: 16,
+            font_scale: 1. / GAME_SCALE as f32,
+            color,
+            ..Default::default()
+        },
+    );
+}
+
+/// Tick number and simulation speed in the top right corner of the screen
+pub fn visualize_time(tick: u64, time_control: &TimeControl) {
+    const MARGIN: f32 = 8.;
+    const FONT_SIZE: u16 = 16;
+
+    set_default_camera();
+
+    let text = format!("tick {}  {}", tick, time_control.describe());
+    let size = measure_text(&text, None, FONT_SIZE, 1.);
+    let color = if time_control.paused { YELLOW } else { WHITE };
+    draw_text(
+        &text,
+        screen_width() - size.width - MARGIN,
+        MARGIN + size.height,
+        FONT_SIZE as f32,
+        color,
+    );
+}
+
+/// Frame time graph, updates per frame and timings of every span, drawn in screen pixels.
+/// Frames with no update are blue, frames with more than one are orange.
+pub fn visualize_profiler(profiler: &Profiler) {
+    const X: f32 = 8.;
+    const Y: f32 = 8.;
+    const BAR_WIDTH: f32 = 2.;
+    const GRAPH_HEIGHT: f32 = 80.;
+    /// pixels per millisecond
+    const MS_HEIGHT: f32 = 2.;
+    const UPDATE_HEIGHT: f32 = 4.;
+    const STRIP_HEIGHT: f32 = 16.;
+    const LINE_HEIGHT: f32 = 16.;
+
+    if !profiler.visible {
+        return;
+    }
+    set_default_camera();
+
+    let width = GRAPH_LENGTH as f32 * BAR_WIDTH;
+    let bottom = Y + GRAPH_HEIGHT;
+    draw_rectangle(X, Y, width, GRAPH_HEIGHT + STRIP_HEIGHT, Color::new(0., 0., 0., 0.6));
+
+    for (index, frame) in profiler.frames.iter().enumerate() {
+        let x = X + index as f32 * BAR_WIDTH;
+        let color = match frame.updates {
+            0 => BLUE,
+            1 => WHITE,
+            _ => ORANGE,
+        };
+        let height = (frame.time * MS_HEIGHT).min(GRAPH_HEIGHT);
+        draw_rectangle(x, bottom - height, BAR_WIDTH, height, color);
+
+        let updates = (frame.updates as f32 * UPDATE_HEIGHT).min(STRIP_HEIGHT);
+        draw_rectangle(x, bottom + STRIP_HEIGHT - updates, BAR_WIDTH, updates, color);
+    }
+    // budget of a single update
+    let budget = bottom - FRAMETIME * 1000. * MS_HEIGHT;
+    draw_line(X, budget, X + width, budget, 1., GREEN);
+
+    let mut y = bottom + STRIP_HEIGHT + LINE_HEIGHT;
+    if let Some(frame) = profiler.frames.get(-1) {
+        let line = format!(
+            "frame {:.2} ms  updates {}  entities {}  colliders {}{}",
+            frame.time,
+            frame.updates,
+            frame.entities,
+            frame.colliders,
+            if profiler.capturing() { "  [capturing]" } else { "" }
+        );
+        draw_text(&line, X, y, LINE_HEIGHT, WHITE);
+        y += LINE_HEIGHT;
+    }
+    draw_text("ms                 min    avg    max", X, y, LINE_HEIGHT, WHITE);
+    for (name, stats) in profiler.spans() {
+        y += LINE_HEIGHT;
+        let line =
+            format!("{:<18} {:>6.3} {:>6.3} {:>6.3}", name, stats.min(), stats.avg(), stats.max());
+        draw_text(&line, X, y, LINE_HEIGHT, WHITE);
+    }
+}
+
+/// Drop-down console over the top half of the screen, newest lines at the bottom
+pub fn draw_console(console: &Console) {
+    const MARGIN: f32 = 8.;
+    const LINE_HEIGHT: f32 = 16.;
+
+    if !console.open {
+        return;
+    }
+    set_default_camera();
+
+    let height = screen_height() / 2.;
+    draw_rectangle(0., 0., screen_width(), height, Color::new(0., 0., 0., 0.8));
+
+    let mut y = height - MARGIN;
+    draw_text(&format!("> {}_", console.input()), MARGIN, y, LINE_HEIGHT, YELLOW);
+    for line in console.scrollback().rev() {
+        y -= LINE_HEIGHT;
+        if y < LINE_HEIGHT {
+            break;
+        }
+        draw_text(line, MARGIN, y, LINE_HEIGHT, WHITE);
+    }
+}
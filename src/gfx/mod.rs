@@ -6,21 +6,25 @@ pub mod debug_info;
 pub use self::animation::*;
 
 pub type AnimationStorage = fxhash::FxHashMap<String, AnimationTemplate>;
-pub type TextureStorage = fxhash::FxHashMap<String, macroquad::texture::Texture2D>;
 
+use crate::assets::{Assets, TextureId};
 use crate::game::Game;
+use crate::particle::Particles;
 use crate::phx::Position;
+use crate::util::lerp_color;
 use crate::GAME_SCALE;
 
 use glam::Vec2;
-use macroquad::camera::set_camera;
-use macroquad::color::{Color, GRAY, WHITE};
+use macroquad::camera::{set_camera, set_default_camera};
+use macroquad::color::{Color, BLACK, GRAY, WHITE};
 use macroquad::math::Rect;
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
 use macroquad::texture::{draw_texture_ex, DrawTextureParams};
-use macroquad::window::clear_background;
+use macroquad::window::{clear_background, screen_height, screen_width};
 
+#[derive(Clone)]
 pub struct Sprite {
-    pub texture: String,
+    pub texture: TextureId,
     /// area of the texture to be drawn
     pub rect: Rect,
     /// offset from the location given by `Position` component, by default the center
@@ -29,19 +33,31 @@ pub struct Sprite {
     pub color: Color,
     /// if true sprite faces left
     pub face_left: bool,
+    /// multiplier of the drawn size, scaling happens around the center of `rect`
+    pub scale: Vec2,
+    pub visible: bool,
 }
 
 impl Sprite {
     /// Offset is centered by default
-    pub fn new(name: String, x: f32, y: f32, width: f32, height: f32) -> Self {
+    pub fn new(texture: TextureId, x: f32, y: f32, width: f32, height: f32) -> Self {
         Self {
-            texture: name,
+            texture,
             rect: Rect::new(x, y, width, height),
             offset: -Vec2::new(width, height) / 2.,
             color: WHITE,
             face_left: false,
+            scale: Vec2::ONE,
+            visible: true,
         }
     }
+    /// Area covered when drawn at the position
+    pub fn bounds(&self, position: Vec2) -> Rect {
+        let size = Vec2::new(self.rect.w, self.rect.h);
+        let dest_size = size * self.scale;
+        let top_left = position + self.offset + (size - dest_size) / 2.;
+        Rect::new(top_left.x, top_left.y, dest_size.x, dest_size.y)
+    }
 }
 
 /// aligns position with the pixel grid of given game scale
@@ -57,25 +73,95 @@ pub fn render(game: &mut Game) {
 
     let query = game.world.query_mut::<(&Position, &Sprite)>();
     for (_eid, (position, sprite)) in query {
-        let texture = game.textures.get(&sprite.texture).unwrap();
-        let rect = sprite.rect;
+        if !sprite.visible {
+            continue;
+        }
+        let (texture, source) = game.assets.texture_region(sprite.texture, sprite.rect);
+        let bounds = sprite.bounds(position.src);
 
         draw_texture_ex(
-            *texture,
-            align2subpixels(position.src.x + sprite.offset.x, GAME_SCALE as f32),
-            align2subpixels(position.src.y + sprite.offset.y, GAME_SCALE as f32),
+            texture,
+            align2subpixels(bounds.x, GAME_SCALE as f32),
+            align2subpixels(bounds.y, GAME_SCALE as f32),
             sprite.color,
             DrawTextureParams {
-                source: Some(rect),
+                source,
+                dest_size: Some(bounds.size()),
                 flip_x: sprite.face_left,
                 ..Default::default()
             },
         );
     }
 
+    draw_particles(&game.resources.particles, &game.assets);
+
     #[cfg(feature = "devtools")]
     {
-        debug_info::visualize_colliders(&game.resources);
-        debug_info::visualize_boxes(&game.resources);
+        let overlays = &game.overlays;
+        if overlays.colliders {
+            debug_info::visualize_colliders(&game.resources);
+        }
+        if overlays.hurtboxes {
+            debug_info::visualize_boxes(&game.resources);
+        }
+        if overlays.state_machines {
+            debug_info::visualize_state_machines(&game.world);
+        }
+        if game.inspector.open {
+            debug_info::visualize_selection(&game.world, game.inspector.selected);
+        }
+        // switch to screen space, so they go last
+        debug_info::visualize_time(game.resources.tick, &game.time_control);
+        debug_info::visualize_profiler(&game.profiler);
+        debug_info::draw_console(&game.console);
+    }
+}
+
+fn draw_particles(particles: &Particles, assets: &Assets) {
+    for particle in particles.iter() {
+        let template = particles.template(particle.template);
+        let color =
+            lerp_color(template.color_start, template.color_end, particle.age / template.lifetime);
+
+        match template.texture {
+            Some((texture_id, rect)) => {
+                let (texture, source) = assets.texture_region(texture_id, rect);
+                draw_texture_ex(
+                    texture,
+                    align2subpixels(particle.position.x - rect.w / 2., GAME_SCALE as f32),
+                    align2subpixels(particle.position.y - rect.h / 2., GAME_SCALE as f32),
+                    color,
+                    DrawTextureParams {
+                        source,
+                        dest_size: Some(rect.size()),
+                        ..Default::default()
+                    },
+                );
+            }
+            None => {
+                let half_size = template.size / 2.;
+                draw_rectangle(
+                    align2subpixels(particle.position.x - half_size, GAME_SCALE as f32),
+                    align2subpixels(particle.position.y - half_size, GAME_SCALE as f32),
+                    template.size,
+                    template.size,
+                    color,
+                );
+            }
+        }
     }
 }
+
+/// Progress bar in the middle of the screen, `progress` in range 0..=1
+pub fn draw_loading_screen(progress: f32) {
+    const WIDTH: f32 = 240.;
+    const HEIGHT: f32 = 12.;
+
+    clear_background(BLACK);
+    set_default_camera();
+
+    let x = (screen_width() - WIDTH) / 2.;
+    let y = (screen_height() - HEIGHT) / 2.;
+    draw_rectangle_lines(x, y, WIDTH, HEIGHT, 2., WHITE);
+    draw_rectangle(x, y, WIDTH * progress.clamp(0., 1.), HEIGHT, WHITE);
+}
@@ -1,10 +1,12 @@
 use macroquad::prelude::Conf;
 use macroquad::window::next_frame;
 
+mod assets;
 mod effect;
 mod game;
 mod gfx;
 mod map;
+mod particle;
 mod phx;
 mod util;
 
@@ -28,21 +30,29 @@ fn window_conf() -> Conf {
 
 #[macroquad::main(window_conf)]
 async fn main() {
+    #[cfg(feature = "devtools")]
+    let log_buffer = game::devtools::console::LogBuffer::new();
     if cfg!(not(target_arch = "wasm32")) {
-        simple_logger::SimpleLogger::new().init().expect("Logger failed");
+        let logger = simple_logger::SimpleLogger::new();
+        // records are copied to the console scrollback
+        #[cfg(feature = "devtools")]
+        let logger = game::devtools::console::ConsoleLogger::new(logger, log_buffer.clone());
+        logger.init().expect("Logger failed");
     }
     let mut game = game::Game::new();
+    #[cfg(feature = "devtools")]
+    game.console.attach_log(log_buffer);
     game.init().await;
 
-    let mut update_timer = util::FrameTimer::with_fps(UPDATE_RATE as f64);
+    let timing = util::timer::TimingConfig::load();
+    log::info!("Using {:?} timestep", timing.strategy);
+    let mut update_timer = util::FrameTimer::with_fps(UPDATE_RATE as f64, &timing);
 
-    let mut histogram = util::timer::UpdateHistogram::new();
-    let use_histogram = false;
-
-    update_timer.time_snapping = false;
-    update_timer.time_averaging = true;
     let mut resync = true;
     loop {
+        #[cfg(feature = "devtools")]
+        let single_step = game::devtools::begin_frame(&mut game, &mut update_timer);
+
         update_timer.get_time();
 
         // read time only once per frame
@@ -53,20 +63,31 @@ async fn main() {
             resync = false;
         }
 
-        while update_timer.fuzzy_tick() {
+        #[cfg(feature = "devtools")]
+        game.profiler.begin_frame();
+
+        while update_timer.tick() {
             // execute schedule here
             game.update();
-            histogram.register_update();
+        }
+        #[cfg(feature = "devtools")]
+        {
+            if single_step {
+                game.update();
+            }
         }
 
         {
             // unrestrained drawing
+            #[cfg(feature = "devtools")]
+            let render_start = std::time::Instant::now();
             crate::gfx::render(&mut game);
+            #[cfg(feature = "devtools")]
+            game.profiler.record_span("render", render_start, render_start.elapsed());
         }
 
-        if use_histogram {
-            histogram.tick();
-        }
+        #[cfg(feature = "devtools")]
+        game::devtools::end_frame(&mut game);
 
         next_frame().await
     }
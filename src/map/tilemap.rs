@@ -1,13 +1,56 @@
-use ldtk_rust::Project;
+use std::fmt;
 
-const BASE_DIR: &str = "media/tilemap/";
-const TEST_MAP: &str = "test2.ldtk";
-const MAGIC_ID: i64 = 25;
-const MAGIC_LAYER: &str = "Front";
+use ldtk_rust::{Level, Project};
+
+use crate::assets::manifest::MapDef;
 
 // TODO: Better representation, define entry points in the LDtk file, replace module with `Stage` definition
 //  and extract useful informations from the ldtk files.
 
+#[derive(Debug)]
+pub enum TilemapError {
+    Io(String, std::io::Error),
+    /// not a valid LDtk project, e.g. a file that's still being written
+    Parse(String, serde_json::Error),
+    MissingLevel(i64),
+    MissingLayer(String),
+    /// the layer has no columns, so its grid can't be split into rows
+    EmptyLayer(String),
+}
+
+impl fmt::Display for TilemapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TilemapError::Io(path, err) => write!(f, "couldn't access {}: {}", path, err),
+            TilemapError::Parse(path, err) => write!(f, "invalid map {}: {}", path, err),
+            TilemapError::MissingLevel(id) => write!(f, "level {} doesn't exist", id),
+            TilemapError::MissingLayer(name) => write!(f, "no {} layer instance found", name),
+            TilemapError::EmptyLayer(name) => write!(f, "layer {} has no columns", name),
+        }
+    }
+}
+
+/// Loads the level the map refers to out of its project
+#[cfg(feature = "devtools")]
+pub fn load_level(def: &MapDef) -> Result<Level, TilemapError> {
+    let path = &def.path;
+    let text = std::fs::read_to_string(path).map_err(|err| TilemapError::Io(path.clone(), err))?;
+    parse_level(def, &text)
+}
+
+/// Level the map refers to out of a project already read from its file
+pub fn parse_level(def: &MapDef, text: &str) -> Result<Level, TilemapError> {
+    // `Project::new` panics on a broken file, only a single file project is supported anyway
+    let project: Project =
+        serde_json::from_str(text).map_err(|err| TilemapError::Parse(def.path.clone(), err))?;
+
+    project
+        .levels
+        .into_iter()
+        .find(|level| level.uid == def.level)
+        .ok_or(TilemapError::MissingLevel(def.level))
+}
+
 // Tilesize is 16x16
 pub struct Tilemap {
     pub width: u32,
@@ -16,28 +59,17 @@ pub struct Tilemap {
 }
 
 impl Tilemap {
-    // Loads one layer of one specified above tilemap only
-    pub fn load() -> Result<Self, &'static str> {
-        let project = Project::new(BASE_DIR.to_owned() + TEST_MAP);
-
-        let mut current_level_index: Option<usize> = None;
-
-        for (idx, level) in project.levels.iter().enumerate() {
-            if level.uid == MAGIC_ID {
-                current_level_index = Some(idx);
-                break;
-            }
+    // Reads one layer of the level only
+    pub fn from_level(level: &Level, layer: &str) -> Result<Self, TilemapError> {
+        let layer_instance = level
+            .layer_instances
+            .as_ref()
+            .and_then(|layers| layers.iter().find(|li| li.identifier == layer))
+            .ok_or_else(|| TilemapError::MissingLayer(layer.to_owned()))?;
+        if layer_instance.c_wid <= 0 {
+            return Err(TilemapError::EmptyLayer(layer.to_owned()));
         }
 
-        let layer_instance = project.levels
-            [current_level_index.unwrap_or_else(|| panic!("Level {} doesn't exist", MAGIC_ID))]
-        .layer_instances
-        .as_ref()
-        .expect("No layer instances found")
-        .iter()
-        .find(|li| li.identifier == MAGIC_LAYER)
-        .unwrap_or_else(|| panic!("No {} layer instance found.", MAGIC_LAYER));
-
         Ok(Tilemap {
             width: layer_instance.c_wid as u32,
             height: layer_instance.c_hei as u32,
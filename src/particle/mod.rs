@@ -0,0 +1,191 @@
+/*!
+CPU simulated particles. They live in a single compact buffer instead of being entities,
+`Emitter` components and gameplay code only push new particles into it.
+*/
+use fxhash::FxHashMap;
+use glam::Vec2;
+use hecs::World;
+use macroquad::color::Color;
+use macroquad::math::Rect;
+use macroquad::rand::gen_range;
+
+use crate::assets::TextureId;
+use crate::phx::{LandedEvent, Position};
+use crate::util::events::{EventBus, EventReader};
+use crate::FRAMETIME;
+
+const MAX_PARTICLES: usize = 2048;
+
+/// Describes how particles of a kind look and behave
+pub struct ParticleTemplate {
+    pub lifetime: f32,
+    pub velocity: Vec2,
+    /// random velocity in range -spread..spread added to `velocity` per axis
+    pub velocity_spread: Vec2,
+    pub gravity: Vec2,
+    /// color at the start of the lifetime
+    pub color_start: Color,
+    /// color at the end of the lifetime
+    pub color_end: Color,
+    /// texture and its region, a square of `size` pixels is drawn if not given
+    pub texture: Option<(TextureId, Rect)>,
+    pub size: f32,
+}
+
+pub struct Particle {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub age: f32,
+    /// index of `ParticleTemplate` in `Particles`
+    pub template: usize,
+}
+
+pub struct Particles {
+    templates: Vec<ParticleTemplate>,
+    template_ids: FxHashMap<String, usize>,
+    buffer: Vec<Particle>,
+}
+
+impl Default for Particles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Particles {
+    pub fn new() -> Self {
+        Self {
+            templates: Vec::new(),
+            template_ids: FxHashMap::default(),
+            buffer: Vec::with_capacity(MAX_PARTICLES),
+        }
+    }
+    pub fn insert_template(&mut self, name: &str, template: ParticleTemplate) {
+        if let Some(&id) = self.template_ids.get(name) {
+            self.templates[id] = template;
+        } else {
+            self.template_ids.insert(name.to_owned(), self.templates.len());
+            self.templates.push(template);
+        }
+    }
+    pub fn template(&self, id: usize) -> &ParticleTemplate {
+        &self.templates[id]
+    }
+    /// Spawns `count` particles of the given template at `position`
+    pub fn emit(&mut self, template_name: &str, position: Vec2, count: u32) {
+        let template_id = match self.template_ids.get(template_name) {
+            Some(&id) => id,
+            None => {
+                log::error!("Particle template {} doesn't exist", template_name);
+                return;
+            }
+        };
+        let template = &self.templates[template_id];
+
+        for _ in 0..count {
+            if self.buffer.len() >= MAX_PARTICLES {
+                break;
+            }
+            let spread = template.velocity_spread;
+            let velocity = template.velocity
+                + Vec2::new(gen_range(-spread.x, spread.x), gen_range(-spread.y, spread.y));
+            self.buffer.push(Particle { position, velocity, age: 0., template: template_id });
+        }
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.buffer.iter()
+    }
+    fn simulate(&mut self) {
+        let mut index = 0;
+        while index < self.buffer.len() {
+            let particle = &mut self.buffer[index];
+            let template = &self.templates[particle.template];
+            particle.age += FRAMETIME;
+            particle.velocity += template.gravity * FRAMETIME;
+            particle.position += particle.velocity * FRAMETIME;
+
+            // order doesn't matter, keep the buffer packed
+            if particle.age >= template.lifetime {
+                self.buffer.swap_remove(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+/// Component, continuously spawns particles at the owner's `Position`
+pub struct Emitter {
+    pub template: String,
+    /// particles per second
+    pub rate: f32,
+    /// amount of particles spawned at once by `burst`
+    pub burst: u32,
+    /// offset from the `Position` of the owner
+    pub offset: Vec2,
+    pub active: bool,
+    /// accumulated time
+    acc: f32,
+    pending_burst: u32,
+}
+
+impl Emitter {
+    pub fn new(template: &str, rate: f32, burst: u32, offset: Vec2) -> Self {
+        Self {
+            template: template.to_owned(),
+            rate,
+            burst,
+            offset,
+            active: false,
+            acc: 0.,
+            pending_burst: 0,
+        }
+    }
+    /// Spawns `burst` particles on the next update regardless of being active
+    pub fn burst(&mut self) {
+        self.pending_burst += self.burst;
+    }
+}
+
+pub fn emitter_system(world: &mut World, particles: &mut Particles) {
+    for (_eid, (emitter, position)) in world.query_mut::<(&mut Emitter, &Position)>() {
+        let origin = position.src + emitter.offset;
+
+        let mut count = std::mem::take(&mut emitter.pending_burst);
+        if emitter.active && emitter.rate > 0. {
+            emitter.acc += FRAMETIME;
+            let interval = 1. / emitter.rate;
+            while emitter.acc >= interval {
+                emitter.acc -= interval;
+                count += 1;
+            }
+        } else {
+            emitter.acc = 0.;
+        }
+
+        if count > 0 {
+            particles.emit(&emitter.template, origin, count);
+        }
+    }
+}
+
+/// Distance from the center of a character to its feet
+const FEET_OFFSET: Vec2 = Vec2::new(0., 8.);
+
+/// Puffs of dust on the frame a grounded entity lands
+pub fn landing_dust_system(
+    world: &World,
+    events: &EventBus,
+    reader: &mut EventReader<LandedEvent>,
+    particles: &mut Particles,
+) {
+    for LandedEvent { entity } in events.read(reader) {
+        if let Ok(position) = world.get::<Position>(*entity) {
+            particles.emit("landing_puff", position.src + FEET_OFFSET, 6);
+        }
+    }
+}
+
+pub fn particle_update_system(particles: &mut Particles) {
+    particles.simulate();
+}
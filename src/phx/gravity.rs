@@ -1,19 +1,49 @@
+use crate::game::agent::profile::MovementProfile;
 use crate::game::resources::Resources;
 use crate::phx::{ColliderTag, PhysicsWorld, Velocity};
+use crate::util::events::EventBus;
 use crate::FRAMETIME;
 use glam::Vec2;
 
-use hecs::World;
+use hecs::{Entity, World};
 use resphys::ColliderHandle;
 #[derive(Debug)]
 pub struct Gravity {
-    enabled: bool,
-    strength: Vec2,
+    pub enabled: bool,
+    pub strength: Vec2,
+    /// multiplier of `strength` while moving downwards
+    pub fall_multiplier: f32,
+    /// maximum downwards speed
+    pub terminal_velocity: f32,
+    /// time gravity is suspended for after reaching the apex of a jump
+    pub apex_hang_time: f32,
+    hang_left: f32,
 }
 
 impl Gravity {
     pub fn new(strength: Vec2) -> Self {
-        Self { strength, enabled: true }
+        Self {
+            strength,
+            enabled: true,
+            fall_multiplier: 1.,
+            terminal_velocity: f32::INFINITY,
+            apex_hang_time: 0.,
+            hang_left: 0.,
+        }
+    }
+    pub fn from_profile(profile: &MovementProfile) -> Self {
+        let mut gravity = Self::new(Vec2::new(0., profile.gravity() / 60.));
+        gravity.apply_profile(profile);
+        gravity
+    }
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+    pub fn apply_profile(&mut self, profile: &MovementProfile) {
+        self.strength = Vec2::new(0., profile.gravity() / 60.);
+        self.fall_multiplier = profile.fall_gravity_multiplier;
+        self.terminal_velocity = profile.terminal_velocity;
+        self.apex_hang_time = profile.apex_hang_time;
     }
 }
 
@@ -28,15 +58,34 @@ impl Gravity {
 
 pub fn gravity_system(world: &mut World) {
     // vel: &mut Velocity, gravity: &Gravity
-    let query = &mut world.query::<(&mut Velocity, &Gravity)>();
+    let query = &mut world.query::<(&mut Velocity, &mut Gravity)>();
 
     for (_id, (vel, gravity)) in query.iter() {
         if gravity.enabled {
-            vel.src += gravity.strength * FRAMETIME * 60.;
+            apply_gravity(vel, gravity);
         }
     }
 }
 
+fn apply_gravity(vel: &mut Velocity, gravity: &mut Gravity) {
+    if gravity.hang_left > 0. {
+        gravity.hang_left -= FRAMETIME;
+        return;
+    }
+
+    let rising = vel.src.y < 0.;
+    let multiplier = if rising { 1. } else { gravity.fall_multiplier };
+    vel.src += gravity.strength * multiplier * FRAMETIME * 60.;
+
+    // reached the apex this frame
+    if rising && vel.src.y >= 0. && gravity.apex_hang_time > 0. {
+        vel.src.y = 0.;
+        gravity.hang_left = gravity.apex_hang_time;
+    }
+
+    vel.src.y = vel.src.y.min(gravity.terminal_velocity);
+}
+
 #[derive(Debug)]
 pub struct OnGround {
     pub sensor_handle: ColliderHandle,
@@ -75,12 +124,20 @@ impl OnGround {
     }
 }
 
-// could this be part of gravity system if all components will use both?
-pub fn ground_check_system(world: &mut World, phys_world: &PhysicsWorld) {
-    let query = &mut world.query::<&mut OnGround>();
+/// Published on the update an entity touches the ground after being airborne
+#[derive(Debug, Clone)]
+pub struct LandedEvent {
+    pub entity: Entity,
+}
 
-    for (_id, ground_data) in query.iter() {
-        ground_data.on_ground =
-            phys_world.interactions_of(ground_data.sensor_handle).next().is_some();
+// could this be part of gravity system if all components will use both?
+/// Landing is only reported through `LandedEvent`
+pub fn ground_check_system(world: &mut World, phys_world: &PhysicsWorld, events: &mut EventBus) {
+    for (entity, ground_data) in world.query_mut::<&mut OnGround>() {
+        let on_ground = phys_world.interactions_of(ground_data.sensor_handle).next().is_some();
+        if on_ground && !ground_data.on_ground {
+            events.publish(LandedEvent { entity });
+        }
+        ground_data.on_ground = on_ground;
     }
 }
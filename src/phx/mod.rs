@@ -1,6 +1,7 @@
 mod gravity;
 mod hitbox;
 pub mod temp;
+mod wall;
 
 use bitflags::bitflags;
 use glam::Vec2;
@@ -14,6 +15,7 @@ pub type BodyEntityMap = fxhash::FxHashMap<resphys::BodyHandle, hecs::Entity>;
 pub use gravity::*;
 pub use hitbox::*;
 pub use temp::*;
+pub use wall::*;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ColliderTag {
@@ -0,0 +1,60 @@
+use crate::game::resources::Resources;
+use crate::phx::{ColliderTag, PhysicsWorld};
+use glam::Vec2;
+
+use hecs::World;
+use resphys::ColliderHandle;
+
+#[derive(Debug)]
+pub struct OnWall {
+    pub left_sensor: ColliderHandle,
+    pub right_sensor: ColliderHandle,
+    /// touching a wall on the left side
+    pub left: bool,
+    /// touching a wall on the right side
+    pub right: bool,
+}
+
+impl OnWall {
+    /// Places sensors 1 pixel to the left and right of the collider that it is supposed to check for.
+    /// The sensors are 1 pixel wide and collider-1 pixels tall to not trigger on floors and ceilings.
+    pub fn new(resources: &mut Resources, checked_chandle: ColliderHandle) -> Self {
+        let physics = &mut resources.phys;
+        let bodies = &mut resources.phys_bodies;
+        let colliders = &mut resources.phys_colliders;
+
+        let (sensors, owner) = {
+            let checked_collider = &colliders[checked_chandle];
+            let half_exts = checked_collider.shape.half_exts;
+
+            let sensor = |side: f32| {
+                let offset = checked_collider.offset + Vec2::new(side * (half_exts.x + 0.5), 0.);
+                resphys::builder::ColliderDesc::new(
+                    resphys::AABB { half_exts: Vec2::new(0.5, half_exts.y - 0.5) },
+                    ColliderTag::Player,
+                )
+                .sensor()
+                .with_offset(offset)
+                .with_mask(super::Category::GROUND.bits())
+            };
+            ((sensor(-1.), sensor(1.)), checked_collider.owner)
+        };
+
+        let left_sensor = colliders.insert(sensors.0.build(owner), bodies, physics).unwrap();
+        let right_sensor = colliders.insert(sensors.1.build(owner), bodies, physics).unwrap();
+        Self { left_sensor, right_sensor, left: false, right: false }
+    }
+    /// Direction pointing away from the touched wall, 0 if there's none or both sides touch
+    pub fn away_direction(&self) -> f32 {
+        (self.left as i8 - self.right as i8) as f32
+    }
+}
+
+pub fn wall_check_system(world: &mut World, phys_world: &PhysicsWorld) {
+    let query = &mut world.query::<&mut OnWall>();
+
+    for (_id, wall_data) in query.iter() {
+        wall_data.left = phys_world.interactions_of(wall_data.left_sensor).next().is_some();
+        wall_data.right = phys_world.interactions_of(wall_data.right_sensor).next().is_some();
+    }
+}
@@ -0,0 +1,177 @@
+/*!
+Mapping of physical inputs (keys, gamepad buttons and sticks) onto game `Button`s.
+Bindings are stored in a RON config file so they survive rebinding at runtime.
+*/
+use enum_map::EnumMap;
+use fxhash::FxHashMap;
+use macroquad::input::KeyCode;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::input::Button;
+
+pub const INPUT_CONFIG_PATH: &str = "config/input.ron";
+
+/// Gamepad buttons, named after their position on the controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl PadButton {
+    pub const ALL: [PadButton; 16] = [
+        PadButton::South,
+        PadButton::East,
+        PadButton::North,
+        PadButton::West,
+        PadButton::LeftTrigger,
+        PadButton::LeftTrigger2,
+        PadButton::RightTrigger,
+        PadButton::RightTrigger2,
+        PadButton::Select,
+        PadButton::Start,
+        PadButton::LeftThumb,
+        PadButton::RightThumb,
+        PadButton::DPadUp,
+        PadButton::DPadDown,
+        PadButton::DPadLeft,
+        PadButton::DPadRight,
+    ];
+}
+
+/// Analog gamepad axes, positive is right for X and up for Y
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+}
+
+impl PadAxis {
+    pub const ALL: [PadAxis; 4] =
+        [PadAxis::LeftStickX, PadAxis::LeftStickY, PadAxis::RightStickX, PadAxis::RightStickY];
+}
+
+/// A single physical input that can drive a `Button`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PhysicalInput {
+    Key(#[serde(with = "key_code")] KeyCode),
+    Pad(PadButton),
+    /// counts as pressed when the axis is pushed past the deadzone in the given direction
+    Axis {
+        axis: PadAxis,
+        positive: bool,
+    },
+}
+
+/// Missing fields are taken from the default bindings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+    /// analog values below this magnitude are ignored
+    pub deadzone: f32,
+    #[serde(deserialize_with = "buttons_or_default")]
+    pub buttons: EnumMap<Button, Vec<PhysicalInput>>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use PhysicalInput::{Axis, Key, Pad};
+
+        let mut buttons = EnumMap::<Button, Vec<PhysicalInput>>::default();
+        buttons[Button::Up] = vec![
+            Key(KeyCode::Up),
+            Pad(PadButton::DPadUp),
+            Axis { axis: PadAxis::LeftStickY, positive: true },
+        ];
+        buttons[Button::Left] = vec![
+            Key(KeyCode::Left),
+            Pad(PadButton::DPadLeft),
+            Axis { axis: PadAxis::LeftStickX, positive: false },
+        ];
+        buttons[Button::Down] = vec![
+            Key(KeyCode::Down),
+            Pad(PadButton::DPadDown),
+            Axis { axis: PadAxis::LeftStickY, positive: false },
+        ];
+        buttons[Button::Right] = vec![
+            Key(KeyCode::Right),
+            Pad(PadButton::DPadRight),
+            Axis { axis: PadAxis::LeftStickX, positive: true },
+        ];
+        buttons[Button::Jump] = vec![Key(KeyCode::Space), Pad(PadButton::South)];
+        buttons[Button::Attack] = vec![Key(KeyCode::Z), Pad(PadButton::West)];
+        buttons[Button::Dash] =
+            vec![Key(KeyCode::X), Pad(PadButton::East), Pad(PadButton::RightTrigger)];
+        Self { deadzone: 0.3, buttons }
+    }
+}
+
+/// Buttons missing from the file, e.g. ones added after it was saved, keep their default inputs
+fn buttons_or_default<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<EnumMap<Button, Vec<PhysicalInput>>, D::Error> {
+    let mut buttons = InputBindings::default().buttons;
+    for (button, inputs) in FxHashMap::<Button, Vec<PhysicalInput>>::deserialize(deserializer)? {
+        buttons[button] = inputs;
+    }
+    Ok(buttons)
+}
+
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        fn key_from_name(name: &str) -> Option<KeyCode> {
+            match name {
+                $(stringify!($key) => Some(KeyCode::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+key_names! {
+    Space, Apostrophe, Comma, Minus, Period, Slash,
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+    Semicolon, Equal,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    LeftBracket, Backslash, RightBracket, GraveAccent, World1, World2,
+    Escape, Enter, Tab, Backspace, Insert, Delete, Right, Left, Down, Up,
+    PageUp, PageDown, Home, End, CapsLock, ScrollLock, NumLock, PrintScreen, Pause,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24, F25,
+    Kp0, Kp1, Kp2, Kp3, Kp4, Kp5, Kp6, Kp7, Kp8, Kp9,
+    KpDecimal, KpDivide, KpMultiply, KpSubtract, KpAdd, KpEnter, KpEqual,
+    LeftShift, LeftControl, LeftAlt, LeftSuper,
+    RightShift, RightControl, RightAlt, RightSuper, Menu,
+}
+
+/// `KeyCode` doesn't implement serde traits, store it by its name instead
+mod key_code {
+    use macroquad::input::KeyCode;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:?}", key))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        super::key_from_name(&name).ok_or_else(|| D::Error::custom(format!("unknown key {}", name)))
+    }
+}
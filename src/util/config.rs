@@ -0,0 +1,49 @@
+/*!
+Loading and saving of RON config files.
+*/
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(String, std::io::Error),
+    Parse(String, ron::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "couldn't access {}: {}", path, err),
+            ConfigError::Parse(path, err) => write!(f, "invalid config {}: {}", path, err),
+        }
+    }
+}
+
+pub fn load<T: DeserializeOwned>(path: &str) -> Result<T, ConfigError> {
+    let text = std::fs::read_to_string(path).map_err(|err| ConfigError::Io(path.into(), err))?;
+    parse(path, &text)
+}
+
+/// Parses config text that didn't come from a file, e.g. one embedded in the binary
+pub fn parse<T: DeserializeOwned>(name: &str, text: &str) -> Result<T, ConfigError> {
+    ron::from_str(text).map_err(|err| ConfigError::Parse(name.into(), err))
+}
+
+/// Loads the config file, falling back to the default if it's missing or broken
+pub fn load_or_default<T: DeserializeOwned + Default>(path: &str) -> T {
+    load(path).unwrap_or_else(|err| {
+        log::warn!("Using default config, {}", err);
+        T::default()
+    })
+}
+
+pub fn save<T: Serialize>(path: &str, value: &T) -> Result<(), ConfigError> {
+    let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|err| ConfigError::Parse(path.into(), err))?;
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(dir).map_err(|err| ConfigError::Io(path.into(), err))?;
+    }
+    std::fs::write(path, text).map_err(|err| ConfigError::Io(path.into(), err))
+}
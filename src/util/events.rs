@@ -0,0 +1,279 @@
+/*!
+Typed events shared between systems.
+
+An event lives for exactly one tick: it's dropped right before the system that published it runs
+again, so every other system runs exactly once while it's around, no matter if it comes before or
+after the publisher. `EventReader` makes sure a system that reads every tick sees each event only
+once. A reader can claim events instead, taking them out of the bus so nobody else handles them.
+Events published outside of the schedule, e.g. while loading a map, live through the next tick.
+*/
+use std::any::{Any, TypeId};
+use std::marker::PhantomData;
+
+use fxhash::FxHashMap;
+
+/// Anything that can be published
+pub trait Event: Clone + Send + Sync + 'static {}
+
+impl<T: Clone + Send + Sync + 'static> Event for T {}
+
+/// When and by which system, counted by its position in the schedule, an event was published
+#[derive(Clone, Copy)]
+struct Stamp {
+    tick: u64,
+    position: usize,
+}
+
+impl Stamp {
+    /// A tick has passed once the schedule is back at the publisher
+    fn expired(&self, now: Stamp) -> bool {
+        now.tick > self.tick + 1 || (now.tick == self.tick + 1 && now.position >= self.position)
+    }
+}
+
+struct Channel<T> {
+    /// along with their sequence numbers, oldest first
+    events: Vec<(u64, Stamp, T)>,
+    next_id: u64,
+    /// everything published during this tick, claimed events included
+    #[cfg(feature = "devtools")]
+    published: Vec<T>,
+}
+
+impl<T: Event> Channel<T> {
+    fn new() -> Self {
+        Self {
+            events: vec![],
+            next_id: 0,
+            #[cfg(feature = "devtools")]
+            published: vec![],
+        }
+    }
+}
+
+/// Lets the bus update its channels without knowing their event types
+trait AnyChannel: Send + Sync {
+    fn begin_tick(&mut self);
+    fn drop_expired(&mut self, now: Stamp);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Event> AnyChannel for Channel<T> {
+    fn begin_tick(&mut self) {
+        #[cfg(feature = "devtools")]
+        self.published.clear();
+    }
+    fn drop_expired(&mut self, now: Stamp) {
+        self.events.retain(|(_, stamp, _)| !stamp.expired(now));
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Remembers which events were already read, for systems that read every tick.
+pub struct EventReader<T> {
+    next_id: u64,
+    _event: PhantomData<fn() -> T>,
+}
+
+impl<T> EventReader<T> {
+    pub fn new() -> Self {
+        Self { next_id: 0, _event: PhantomData }
+    }
+}
+
+pub struct EventBus {
+    channels: FxHashMap<TypeId, Box<dyn AnyChannel>>,
+    now: Stamp,
+    /// false while the schedule is running
+    between_ticks: bool,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            channels: FxHashMap::default(),
+            now: Stamp { tick: 0, position: 0 },
+            between_ticks: true,
+        }
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Called by the schedule at the start of every tick
+    pub fn begin_tick(&mut self) {
+        self.now = Stamp { tick: self.now.tick + 1, position: 0 };
+        self.between_ticks = false;
+        for channel in self.channels.values_mut() {
+            channel.begin_tick();
+        }
+    }
+
+    /// Called by the schedule once every system ran
+    pub fn end_tick(&mut self) {
+        self.between_ticks = true;
+    }
+
+    /// Called by the schedule before every system, disabled ones included,
+    /// drops the events published by the system at this position during the previous tick
+    pub fn begin_system(&mut self, position: usize) {
+        self.now.position = position;
+        for channel in self.channels.values_mut() {
+            channel.drop_expired(self.now);
+        }
+    }
+
+    pub fn publish<T: Event>(&mut self, event: T) {
+        // as if published before the first system of the next tick
+        let now = match self.between_ticks {
+            true => Stamp { tick: self.now.tick + 1, position: 0 },
+            false => self.now,
+        };
+        let channel = self.channel_mut::<T>();
+        #[cfg(feature = "devtools")]
+        channel.published.push(event.clone());
+        channel.events.push((channel.next_id, now, event));
+        channel.next_id += 1;
+    }
+
+    /// Events the reader hasn't seen yet, oldest first
+    pub fn read<'a, T: Event>(
+        &'a self,
+        reader: &mut EventReader<T>,
+    ) -> impl Iterator<Item = &'a T> + 'a {
+        let channel = self.channel::<T>();
+        let from = reader.next_id;
+        if let Some(channel) = channel {
+            reader.next_id = channel.next_id;
+        }
+        channel
+            .into_iter()
+            .flat_map(|channel| channel.events.iter())
+            .filter(move |(id, _, _)| *id >= from)
+            .map(|(_, _, event)| event)
+    }
+
+    /// Takes every unclaimed event out of the bus, oldest first
+    pub fn claim<T: Event>(&mut self) -> Vec<T> {
+        let channel = self.channel_mut::<T>();
+        channel.events.drain(..).map(|(_, _, event)| event).collect()
+    }
+
+    /// Everything published during the last tick, claimed events included
+    #[cfg(feature = "devtools")]
+    pub fn published<T: Event>(&self) -> &[T] {
+        self.channel::<T>().map(|channel| channel.published.as_slice()).unwrap_or(&[])
+    }
+
+    fn channel<T: Event>(&self) -> Option<&Channel<T>> {
+        self.channels
+            .get(&TypeId::of::<T>())
+            .and_then(|channel| channel.as_any().downcast_ref::<Channel<T>>())
+    }
+
+    fn channel_mut<T: Event>(&mut self) -> &mut Channel<T> {
+        self.channels
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Channel::<T>::new()) as Box<dyn AnyChannel>)
+            .as_any_mut()
+            .downcast_mut::<Channel<T>>()
+            .expect("channels are stored under the type ID of their events")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Ping(u32);
+
+    /// Runs `systems` systems, each one gets the bus and its position
+    fn tick(bus: &mut EventBus, systems: usize, mut run: impl FnMut(&mut EventBus, usize)) {
+        bus.begin_tick();
+        for position in 0..systems {
+            bus.begin_system(position);
+            run(bus, position);
+        }
+        bus.end_tick();
+    }
+
+    fn pings(bus: &EventBus) -> Vec<u32> {
+        bus.read(&mut EventReader::<Ping>::new()).map(|ping| ping.0).collect()
+    }
+
+    #[test]
+    fn events_live_until_their_publisher_runs_again() {
+        let mut bus = EventBus::new();
+        let mut seen = vec![];
+        tick(&mut bus, 3, |bus, position| match position {
+            1 => bus.publish(Ping(1)),
+            _ => seen.push((position, pings(bus))),
+        });
+        // the system before the publisher missed it this tick
+        assert_eq!(seen, [(0, vec![]), (2, vec![1])]);
+
+        seen.clear();
+        tick(&mut bus, 3, |bus, position| seen.push((position, pings(bus))));
+        assert_eq!(seen, [(0, vec![1]), (1, vec![]), (2, vec![])]);
+    }
+
+    #[test]
+    fn events_published_between_ticks_live_through_the_next_one() {
+        let mut bus = EventBus::new();
+        bus.publish(Ping(1));
+        let mut seen = vec![];
+        tick(&mut bus, 2, |bus, position| seen.push((position, pings(bus))));
+        assert_eq!(seen, [(0, vec![1]), (1, vec![1])]);
+
+        seen.clear();
+        tick(&mut bus, 2, |bus, position| seen.push((position, pings(bus))));
+        assert_eq!(seen, [(0, vec![]), (1, vec![])]);
+    }
+
+    #[test]
+    fn expiry_follows_the_publisher_position() {
+        let published = Stamp { tick: 1, position: 2 };
+        assert!(!published.expired(Stamp { tick: 1, position: 3 }));
+        assert!(!published.expired(Stamp { tick: 2, position: 1 }));
+        assert!(published.expired(Stamp { tick: 2, position: 2 }));
+        assert!(published.expired(Stamp { tick: 3, position: 0 }));
+    }
+
+    #[test]
+    fn readers_see_each_event_once() {
+        let mut bus = EventBus::new();
+        let mut reader = EventReader::<Ping>::new();
+        let mut seen = vec![];
+        for _ in 0..2 {
+            tick(&mut bus, 2, |bus, position| match position {
+                0 => bus.publish(Ping(bus.now.tick as u32)),
+                _ => seen.extend(bus.read(&mut reader).map(|ping| ping.0)),
+            });
+        }
+        assert_eq!(seen, [1, 2]);
+    }
+
+    #[test]
+    fn claimed_events_are_gone_for_everyone() {
+        let mut bus = EventBus::new();
+        let mut claimed = vec![];
+        let mut seen = vec![];
+        tick(&mut bus, 3, |bus, position| match position {
+            0 => bus.publish(Ping(1)),
+            1 => claimed = bus.claim::<Ping>(),
+            _ => seen = pings(bus),
+        });
+        assert_eq!(claimed, [Ping(1)]);
+        assert!(seen.is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use fxhash::FxHashMap;
+
+/// Notices changes of a file by polling its modification time
+pub struct FileWatcher {
+    path: String,
+    modified: Option<SystemTime>,
+}
+
+impl FileWatcher {
+    pub fn new(path: &str) -> Self {
+        Self { path: path.to_owned(), modified: modified_time(path) }
+    }
+    /// True once after every change of the file
+    pub fn changed(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        if modified.is_some() && modified != self.modified {
+            self.modified = modified;
+            return true;
+        }
+        false
+    }
+}
+
+fn modified_time(path: impl AsRef<Path>) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Notices changes of every file in a directory and its subdirectories, polled at most once per `interval`
+pub struct DirectoryWatcher {
+    path: String,
+    modified: FxHashMap<PathBuf, SystemTime>,
+    interval: Duration,
+    last_scan: Instant,
+}
+
+impl DirectoryWatcher {
+    pub fn new(path: &str, interval: Duration) -> Self {
+        let mut modified = FxHashMap::default();
+        scan(Path::new(path), &mut modified);
+        Self { path: path.to_owned(), modified, interval, last_scan: Instant::now() }
+    }
+    /// Files created or modified since the last call
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        if self.last_scan.elapsed() < self.interval {
+            return vec![];
+        }
+        self.last_scan = Instant::now();
+
+        let mut modified = FxHashMap::default();
+        scan(Path::new(&self.path), &mut modified);
+        let changed = modified
+            .iter()
+            .filter(|(path, time)| self.modified.get(*path) != Some(time))
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.modified = modified;
+        changed
+    }
+}
+
+fn scan(dir: &Path, modified: &mut FxHashMap<PathBuf, SystemTime>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            scan(&path, modified);
+        } else if let Some(time) = modified_time(&path) {
+            modified.insert(path, time);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+/*!
+Gamepad state, backed by gilrs on native targets.
+On the web there is no backend yet and every gamepad input reads as released.
+*/
+use super::bindings::{PadAxis, PadButton};
+
+pub struct Gamepads {
+    #[cfg(not(target_arch = "wasm32"))]
+    gilrs: Option<gilrs::Gilrs>,
+}
+
+impl Gamepads {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new() -> Self {
+        let gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(err) => {
+                log::warn!("Gamepad support unavailable: {}", err);
+                None
+            }
+        };
+        Self { gilrs }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Processes pending gamepad events, has to be called before reading the state
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn update(&mut self) {
+        if let Some(gilrs) = &mut self.gilrs {
+            while gilrs.next_event().is_some() {}
+        }
+    }
+
+    /// True if the button is held on any connected gamepad
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_down(&self, button: PadButton) -> bool {
+        let button = to_gilrs_button(button);
+        self.gilrs
+            .as_ref()
+            .map_or(false, |gilrs| gilrs.gamepads().any(|(_id, pad)| pad.is_pressed(button)))
+    }
+
+    /// Value of the axis furthest from the center among connected gamepads
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn axis(&self, axis: PadAxis) -> f32 {
+        let axis = to_gilrs_axis(axis);
+        self.gilrs.as_ref().map_or(0., |gilrs| {
+            gilrs.gamepads().map(|(_id, pad)| pad.value(axis)).fold(0., |acc: f32, value| {
+                if value.abs() > acc.abs() {
+                    value
+                } else {
+                    acc
+                }
+            })
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn update(&mut self) {}
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn is_down(&self, _button: PadButton) -> bool {
+        false
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn axis(&self, _axis: PadAxis) -> f32 {
+        0.
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn to_gilrs_button(button: PadButton) -> gilrs::Button {
+    match button {
+        PadButton::South => gilrs::Button::South,
+        PadButton::East => gilrs::Button::East,
+        PadButton::North => gilrs::Button::North,
+        PadButton::West => gilrs::Button::West,
+        PadButton::LeftTrigger => gilrs::Button::LeftTrigger,
+        PadButton::LeftTrigger2 => gilrs::Button::LeftTrigger2,
+        PadButton::RightTrigger => gilrs::Button::RightTrigger,
+        PadButton::RightTrigger2 => gilrs::Button::RightTrigger2,
+        PadButton::Select => gilrs::Button::Select,
+        PadButton::Start => gilrs::Button::Start,
+        PadButton::LeftThumb => gilrs::Button::LeftThumb,
+        PadButton::RightThumb => gilrs::Button::RightThumb,
+        PadButton::DPadUp => gilrs::Button::DPadUp,
+        PadButton::DPadDown => gilrs::Button::DPadDown,
+        PadButton::DPadLeft => gilrs::Button::DPadLeft,
+        PadButton::DPadRight => gilrs::Button::DPadRight,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn to_gilrs_axis(axis: PadAxis) -> gilrs::Axis {
+    match axis {
+        PadAxis::LeftStickX => gilrs::Axis::LeftStickX,
+        PadAxis::LeftStickY => gilrs::Axis::LeftStickY,
+        PadAxis::RightStickX => gilrs::Axis::RightStickX,
+        PadAxis::RightStickY => gilrs::Axis::RightStickY,
+    }
+}
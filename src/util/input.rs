@@ -1,12 +1,18 @@
 /*!
 Module for processing the raw input into easy for the game to reason about information.
-Currently handles only keyboard.
+Handles keyboard and gamepads, see `bindings` for how physical inputs map onto `Button`s.
 */
 use enum_map::{Enum, EnumMap};
-use macroquad::input::{is_key_down, KeyCode};
+use macroquad::input::{get_last_key_pressed, is_key_down};
+use serde::{Deserialize, Serialize};
+
+use super::bindings::{InputBindings, PadAxis, PadButton, PhysicalInput, INPUT_CONFIG_PATH};
+use super::config;
+use super::gamepad::Gamepads;
+use super::input_history::{ButtonHistory, Direction, DirectionHistory, Motion};
 
 /// Treat as if the game had dedicated controller with these buttons.
-#[derive(Debug, Enum, Clone, Copy)]
+#[derive(Debug, Enum, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Button {
     Left,
     Right,
@@ -14,48 +20,156 @@ pub enum Button {
     Down,
     Jump,
     Attack,
+    Dash,
 }
 
 // Reads the edge-based input and turn it into level-based.
 pub struct ButtonsState {
-    bindings: EnumMap<Button, (Option<KeyCode>, u8)>,
+    bindings: InputBindings,
+    history: EnumMap<Button, ButtonHistory>,
+    /// combined direction of the directional buttons, for motion inputs
+    directions: DirectionHistory,
+    gamepads: Gamepads,
+    /// physical input is ignored while set, e.g. while typing into the devtools console
+    pub blocked: bool,
 }
 
 impl Default for ButtonsState {
     fn default() -> Self {
-        let mut bindings = EnumMap::<Button, (Option<KeyCode>, u8)>::default();
-        bindings[Button::Up] = (Some(KeyCode::Up), 0);
-        bindings[Button::Left] = (Some(KeyCode::Left), 0);
-        bindings[Button::Down] = (Some(KeyCode::Down), 0);
-        bindings[Button::Right] = (Some(KeyCode::Right), 0);
-        bindings[Button::Jump] = (Some(KeyCode::Space), 0);
-        bindings[Button::Attack] = (Some(KeyCode::Z), 0);
-        Self { bindings }
+        Self {
+            bindings: InputBindings::default(),
+            history: EnumMap::default(),
+            directions: DirectionHistory::default(),
+            gamepads: Gamepads::new(),
+            blocked: false,
+        }
     }
 }
 
 impl ButtonsState {
+    /// Bindings are read from the input config file, defaults are used if it can't be loaded
     pub fn new() -> Self {
-        Default::default()
+        Self { bindings: config::load_or_default(INPUT_CONFIG_PATH), ..Default::default() }
     }
     pub fn update(&mut self) {
-        for (maybe_key, ref mut history) in self.bindings.values_mut() {
-            if let Some(key) = maybe_key {
-                *history <<= 1;
-                *history |= is_key_down(*key) as u8;
-            }
+        self.gamepads.update();
+
+        for (button, history) in self.history.iter_mut() {
+            let is_down = !self.blocked
+                && self.bindings.buttons[button]
+                    .iter()
+                    .any(|input| input_down(&self.gamepads, self.bindings.deadzone, input));
+            history.push(is_down);
         }
+
+        let direction = Direction::from_buttons(
+            self.is_pressed(Button::Left),
+            self.is_pressed(Button::Right),
+            self.is_pressed(Button::Up),
+            self.is_pressed(Button::Down),
+        );
+        self.directions.push(direction);
     }
     #[allow(dead_code)]
     pub fn is_pressed(&self, button: Button) -> bool {
-        (self.bindings[button].1 & 0b1) == 0b1
+        self.history[button].is_down()
     }
     #[allow(dead_code)]
     pub fn pressed(&self, button: Button) -> bool {
-        (self.bindings[button].1 & 0b11) == 0b01
+        self.history[button].pressed()
     }
     #[allow(dead_code)]
     pub fn released(&self, button: Button) -> bool {
-        (self.bindings[button].1 & 0b11) == 0b10
+        self.history[button].released()
+    }
+    /// Ticks the button is held for, 0 if it's not held
+    #[allow(dead_code)]
+    pub fn held_for(&self, button: Button) -> u32 {
+        self.history[button].held_for()
+    }
+    /// Press happened during the last `ticks` ticks, lets actions be input slightly early
+    #[allow(dead_code)]
+    pub fn pressed_within(&self, button: Button, ticks: u32) -> bool {
+        self.history[button].pressed_within(ticks)
+    }
+    /// Pressed now, and also no more than `window` ticks before
+    #[allow(dead_code)]
+    pub fn double_tapped(&self, button: Button, window: u32) -> bool {
+        self.history[button].double_tapped(window)
+    }
+    #[allow(dead_code)]
+    pub fn direction(&self) -> Direction {
+        self.directions.current()
+    }
+    /// Directions were input in the given order during the last `window` ticks,
+    /// `face_left` decides which way is forward
+    #[allow(dead_code)]
+    pub fn sequence(&self, motions: &[Motion], window: u32, face_left: bool) -> bool {
+        self.directions.sequence(motions, window, face_left)
+    }
+    /// Motion input finished with a button press, e.g. down, down-forward, forward + Attack
+    #[allow(dead_code)]
+    pub fn special_move(
+        &self,
+        motions: &[Motion],
+        button: Button,
+        window: u32,
+        face_left: bool,
+    ) -> bool {
+        self.pressed(button) && self.sequence(motions, window, face_left)
+    }
+
+    pub fn bindings(&self) -> &InputBindings {
+        &self.bindings
+    }
+    /// Adds another physical input triggering the button
+    pub fn bind(&mut self, button: Button, input: PhysicalInput) {
+        let inputs = &mut self.bindings.buttons[button];
+        if !inputs.contains(&input) {
+            inputs.push(input);
+        }
+    }
+    pub fn unbind(&mut self, button: Button, input: PhysicalInput) {
+        self.bindings.buttons[button].retain(|bound| *bound != input);
+    }
+    /// Replaces all inputs of the button with a single new one
+    pub fn rebind(&mut self, button: Button, input: PhysicalInput) {
+        self.bindings.buttons[button] = vec![input];
+    }
+    /// Writes the current bindings to the input config file
+    pub fn save_bindings(&self) {
+        if let Err(err) = config::save(INPUT_CONFIG_PATH, &self.bindings) {
+            log::error!("Failed to save input bindings, {}", err);
+        }
+    }
+    /// First physical input activated this frame, used to pick a new binding at runtime
+    pub fn listen(&self) -> Option<PhysicalInput> {
+        if let Some(key) = get_last_key_pressed() {
+            return Some(PhysicalInput::Key(key));
+        }
+        if let Some(&button) = PadButton::ALL.iter().find(|&&button| self.gamepads.is_down(button))
+        {
+            return Some(PhysicalInput::Pad(button));
+        }
+        PadAxis::ALL.iter().find_map(|&axis| {
+            let value = self.gamepads.axis(axis);
+            // require a decisive push so resting sticks don't get bound
+            (value.abs() > 0.5).then(|| PhysicalInput::Axis { axis, positive: value > 0. })
+        })
+    }
+}
+
+fn input_down(gamepads: &Gamepads, deadzone: f32, input: &PhysicalInput) -> bool {
+    match *input {
+        PhysicalInput::Key(key) => is_key_down(key),
+        PhysicalInput::Pad(button) => gamepads.is_down(button),
+        PhysicalInput::Axis { axis, positive } => {
+            let value = gamepads.axis(axis);
+            if positive {
+                value > deadzone
+            } else {
+                value < -deadzone
+            }
+        }
     }
 }
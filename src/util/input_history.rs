@@ -0,0 +1,208 @@
+/*!
+Run-length encoded history of inputs, long enough to recognize double taps and motion inputs.
+Every run remembers a state and for how many ticks it lasted, the newest run is at the back.
+*/
+use std::collections::VecDeque;
+
+/// Amount of state changes remembered
+const HISTORY_RUNS: usize = 32;
+
+struct Run<T> {
+    state: T,
+    ticks: u32,
+}
+
+pub struct History<T> {
+    runs: VecDeque<Run<T>>,
+}
+
+impl<T: Default> Default for History<T> {
+    fn default() -> Self {
+        let mut runs = VecDeque::with_capacity(HISTORY_RUNS);
+        // start with a run lasting 0 ticks so the first update isn't treated as a change
+        runs.push_back(Run { state: T::default(), ticks: 0 });
+        Self { runs }
+    }
+}
+
+impl<T: Copy + PartialEq> History<T> {
+    pub fn push(&mut self, state: T) {
+        let current = self.runs.back_mut().unwrap();
+        if current.state == state {
+            current.ticks = current.ticks.saturating_add(1);
+        } else {
+            if self.runs.len() == HISTORY_RUNS {
+                self.runs.pop_front();
+            }
+            self.runs.push_back(Run { state, ticks: 1 });
+        }
+    }
+    pub fn current(&self) -> T {
+        self.runs.back().unwrap().state
+    }
+    /// Ticks the current state lasted, including this one
+    pub fn current_ticks(&self) -> u32 {
+        self.runs.back().unwrap().ticks
+    }
+    /// True if the state changed this tick
+    pub fn changed(&self) -> bool {
+        self.runs.len() > 1 && self.current_ticks() == 1
+    }
+    /// Runs from the newest to the oldest, as (state, ticks since it began, ticks since it ended)
+    fn runs_rev(&self) -> impl Iterator<Item = (T, u32, u32)> + '_ {
+        self.runs.iter().rev().scan(0u32, |ago, run| {
+            let ended = *ago;
+            *ago = ago.saturating_add(run.ticks);
+            Some((run.state, *ago, ended))
+        })
+    }
+}
+
+pub type ButtonHistory = History<bool>;
+
+impl ButtonHistory {
+    pub fn is_down(&self) -> bool {
+        self.current()
+    }
+    pub fn pressed(&self) -> bool {
+        self.is_down() && self.changed()
+    }
+    pub fn released(&self) -> bool {
+        !self.is_down() && self.changed()
+    }
+    /// Ticks the button is held for, 0 if it's not held
+    pub fn held_for(&self) -> u32 {
+        if self.is_down() {
+            self.current_ticks()
+        } else {
+            0
+        }
+    }
+    /// Ticks passed since the most recent press, 1 on the tick of pressing
+    pub fn ticks_since_pressed(&self) -> Option<u32> {
+        self.press_ages().next()
+    }
+    /// Press happened during the last `ticks` ticks, used to buffer inputs
+    pub fn pressed_within(&self, ticks: u32) -> bool {
+        self.ticks_since_pressed().map_or(false, |ago| ago <= ticks)
+    }
+    /// Pressed this tick, with the previous press no more than `window` ticks earlier
+    pub fn double_tapped(&self, window: u32) -> bool {
+        self.pressed() && self.press_ages().nth(1).map_or(false, |ago| ago <= window)
+    }
+    /// Ticks passed since each remembered press, newest first
+    fn press_ages(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut runs = self.runs_rev().map(|(down, ago, _)| (down, ago)).peekable();
+        std::iter::from_fn(move || {
+            while let Some((down, ago)) = runs.next() {
+                // a press is a down run preceded by an up run
+                if down && matches!(runs.peek(), Some((false, _))) {
+                    return Some(ago);
+                }
+            }
+            None
+        })
+    }
+}
+
+/// Absolute direction held on the d-pad or stick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Neutral,
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Default for Direction {
+    fn default() -> Self {
+        Direction::Neutral
+    }
+}
+
+impl Direction {
+    pub fn from_buttons(left: bool, right: bool, up: bool, down: bool) -> Self {
+        let x = right as i8 - left as i8;
+        let y = down as i8 - up as i8;
+        match (x, y) {
+            (0, -1) => Direction::Up,
+            (0, 1) => Direction::Down,
+            (-1, 0) => Direction::Left,
+            (1, 0) => Direction::Right,
+            (-1, -1) => Direction::UpLeft,
+            (1, -1) => Direction::UpRight,
+            (-1, 1) => Direction::DownLeft,
+            (1, 1) => Direction::DownRight,
+            _ => Direction::Neutral,
+        }
+    }
+}
+
+/// Direction relative to where the character faces, used to describe motion inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Neutral,
+    Up,
+    Down,
+    Forward,
+    Back,
+    UpForward,
+    UpBack,
+    DownForward,
+    DownBack,
+}
+
+impl Motion {
+    pub fn to_direction(self, face_left: bool) -> Direction {
+        let (forward, back, up_forward, up_back, down_forward, down_back) = if face_left {
+            use Direction::*;
+            (Left, Right, UpLeft, UpRight, DownLeft, DownRight)
+        } else {
+            use Direction::*;
+            (Right, Left, UpRight, UpLeft, DownRight, DownLeft)
+        };
+        match self {
+            Motion::Neutral => Direction::Neutral,
+            Motion::Up => Direction::Up,
+            Motion::Down => Direction::Down,
+            Motion::Forward => forward,
+            Motion::Back => back,
+            Motion::UpForward => up_forward,
+            Motion::UpBack => up_back,
+            Motion::DownForward => down_forward,
+            Motion::DownBack => down_back,
+        }
+    }
+}
+
+pub type DirectionHistory = History<Direction>;
+
+impl DirectionHistory {
+    /// Checks if the motions were input in order, all of them during the last `window` ticks.
+    /// Other directions in between are tolerated to make sequences easier to perform.
+    pub fn sequence(&self, motions: &[Motion], window: u32, face_left: bool) -> bool {
+        let mut expected = motions.iter().rev().map(|motion| motion.to_direction(face_left));
+        let mut next = match expected.next() {
+            Some(direction) => direction,
+            None => return true,
+        };
+
+        for (direction, _began, ended) in self.runs_rev() {
+            if ended > window {
+                break;
+            }
+            if direction == next {
+                match expected.next() {
+                    Some(direction) => next = direction,
+                    None => return true,
+                }
+            }
+        }
+        false
+    }
+}
@@ -1,3 +1,51 @@
+use macroquad::color::Color;
+
 pub fn lerp(a: f32, b: f32, rate: f32) -> f32 {
     a * (1. - rate) + b * rate
 }
+
+pub fn lerp_color(a: Color, b: Color, rate: f32) -> Color {
+    Color::new(
+        lerp(a.r, b.r, rate),
+        lerp(a.g, b.g, rate),
+        lerp(a.b, b.b, rate),
+        lerp(a.a, b.a, rate),
+    )
+}
+
+/// Easing curves remapping linear progress in range 0..=1
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicOut,
+    /// overshoots slightly before settling, good for squash and stretch
+    BackOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1. - (1. - t) * (1. - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2. * t * t
+                } else {
+                    1. - (-2. * t + 2.).powi(2) / 2.
+                }
+            }
+            Easing::CubicOut => 1. - (1. - t).powi(3),
+            Easing::BackOut => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.;
+                1. + C3 * (t - 1.).powi(3) + C1 * (t - 1.).powi(2)
+            }
+        }
+    }
+}
@@ -1,10 +1,21 @@
+pub mod bindings;
 pub mod camera;
+pub mod config;
+pub mod events;
+pub mod file_watcher;
+pub mod gamepad;
 pub mod input;
+pub mod input_history;
 pub mod interpolation;
+#[cfg(feature = "devtools")]
+pub mod profiler;
 pub mod state_machine;
+#[cfg(feature = "devtools")]
+pub mod time_control;
 pub mod timer;
+pub mod transition_log;
 
 pub use camera::Camera;
 pub use input::ButtonsState;
-pub use interpolation::lerp;
+pub use interpolation::{lerp, lerp_color, Easing};
 pub use timer::FrameTimer;
@@ -0,0 +1,204 @@
+/*!
+Timings of systems and rendering, along with per-frame counters shown by the devtools overlay.
+
+A capture records every span and frame between its start and stop,
+it can be saved as CSV or as JSON readable by `chrome://tracing` and Perfetto.
+*/
+use std::time::{Duration, Instant};
+
+use ringbuffer::{ConstGenericRingBuffer, RingBuffer, RingBufferExt, RingBufferWrite};
+
+const STATS_WINDOW: usize = 64;
+pub const GRAPH_LENGTH: usize = 128;
+
+/// Min/avg/max of the most recent samples, in milliseconds
+pub struct RollingStats {
+    samples: ConstGenericRingBuffer<f32, STATS_WINDOW>,
+}
+
+impl RollingStats {
+    fn new() -> Self {
+        Self { samples: ConstGenericRingBuffer::new() }
+    }
+    fn push(&mut self, duration: Duration) {
+        self.samples.push(duration.as_secs_f32() * 1000.);
+    }
+    pub fn min(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.;
+        }
+        self.samples.iter().copied().fold(f32::INFINITY, f32::min)
+    }
+    pub fn avg(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.;
+        }
+        self.samples.iter().sum::<f32>() / self.samples.len() as f32
+    }
+    pub fn max(&self) -> f32 {
+        self.samples.iter().copied().fold(0., f32::max)
+    }
+}
+
+/// Counters of a single frame
+#[derive(Debug, Clone, Copy)]
+pub struct FrameSample {
+    /// milliseconds since the previous frame started
+    pub time: f32,
+    pub updates: u32,
+    pub entities: u32,
+    pub colliders: u32,
+}
+
+struct Span {
+    name: &'static str,
+    frame: u64,
+    /// since the capture started
+    start: Duration,
+    duration: Duration,
+}
+
+pub struct Capture {
+    start: Instant,
+    first_frame: u64,
+    spans: Vec<Span>,
+    frames: Vec<(Duration, FrameSample)>,
+}
+
+impl Capture {
+    /// A row for every span
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("frame,name,start_us,duration_us\n");
+        for span in self.spans.iter() {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                span.frame - self.first_frame,
+                span.name,
+                span.start.as_micros(),
+                span.duration.as_micros()
+            ));
+        }
+        csv
+    }
+
+    /// Chrome trace event format, spans as complete events and frame counters as counter events
+    pub fn to_chrome_trace(&self) -> String {
+        let mut events = vec![];
+        for span in self.spans.iter() {
+            events.push(format!(
+                r#"{{"name":"{}","cat":"{}","ph":"X","ts":{},"dur":{},"pid":0,"tid":0}}"#,
+                span.name,
+                if span.name == "render" { "render" } else { "system" },
+                span.start.as_micros(),
+                span.duration.as_micros()
+            ));
+        }
+        for (start, frame) in self.frames.iter() {
+            events.push(format!(
+                r#"{{"name":"frame","ph":"C","ts":{},"pid":0,"args":{{"updates":{},"entities":{},"colliders":{}}}}}"#,
+                start.as_micros(),
+                frame.updates,
+                frame.entities,
+                frame.colliders
+            ));
+        }
+        format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+    }
+}
+
+pub struct Profiler {
+    /// in order they were first recorded
+    spans: Vec<(&'static str, RollingStats)>,
+    pub frames: ConstGenericRingBuffer<FrameSample, GRAPH_LENGTH>,
+    frame: u64,
+    frame_start: Option<Instant>,
+    frame_time: Duration,
+    updates: u32,
+    capture: Option<Capture>,
+    pub visible: bool,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            spans: vec![],
+            frames: ConstGenericRingBuffer::new(),
+            frame: 0,
+            frame_start: None,
+            frame_time: Duration::ZERO,
+            updates: 0,
+            capture: None,
+            visible: false,
+        }
+    }
+
+    pub fn begin_frame(&mut self) {
+        let now = Instant::now();
+        if let Some(frame_start) = self.frame_start {
+            self.frame_time = now - frame_start;
+        }
+        self.frame_start = Some(now);
+        self.frame += 1;
+        self.updates = 0;
+    }
+
+    pub fn end_frame(&mut self, entities: u32, colliders: u32) {
+        let sample = FrameSample {
+            time: self.frame_time.as_secs_f32() * 1000.,
+            updates: self.updates,
+            entities,
+            colliders,
+        };
+        self.frames.push(sample);
+
+        if let (Some(capture), Some(frame_start)) = (&mut self.capture, self.frame_start) {
+            capture.frames.push((frame_start.saturating_duration_since(capture.start), sample));
+        }
+    }
+
+    /// Counts an update, its systems are recorded as spans
+    pub fn record_update(&mut self) {
+        self.updates += 1;
+    }
+
+    pub fn record_span(&mut self, name: &'static str, start: Instant, duration: Duration) {
+        match self.spans.iter_mut().find(|(span_name, _)| *span_name == name) {
+            Some((_, stats)) => stats.push(duration),
+            None => {
+                let mut stats = RollingStats::new();
+                stats.push(duration);
+                self.spans.push((name, stats));
+            }
+        }
+
+        if let Some(capture) = &mut self.capture {
+            capture.spans.push(Span {
+                name,
+                frame: self.frame,
+                start: start.saturating_duration_since(capture.start),
+                duration,
+            });
+        }
+    }
+
+    pub fn spans(&self) -> impl Iterator<Item = &(&'static str, RollingStats)> {
+        self.spans.iter()
+    }
+
+    pub fn capturing(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    pub fn start_capture(&mut self) {
+        self.capture = Some(Capture {
+            start: Instant::now(),
+            first_frame: self.frame,
+            spans: vec![],
+            frames: vec![],
+        });
+    }
+
+    pub fn stop_capture(&mut self) -> Option<Capture> {
+        self.capture.take()
+    }
+}
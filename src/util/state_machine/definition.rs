@@ -0,0 +1,327 @@
+/*!
+State machine graphs described in RON files.
+
+Actions and conditions are referred to by name and looked up in a `Registry` of Rust functions,
+some of them take parameters given in the file. Durations the code also depends on are
+registered by name too, so the file can't disagree with it.
+*/
+use std::collections::HashMap;
+use std::fmt;
+
+use hecs::{Entity, World};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use super::{
+    always, Condition, ContextFn, Labeled, State, StateId, StateMachine, StateMachineBuilder,
+    Timing,
+};
+use crate::game::resources::Resources;
+use crate::util::config::{self, ConfigError};
+
+#[derive(Debug, Deserialize)]
+pub struct MachineDef<S> {
+    pub initial: S,
+    pub states: Vec<StateDef<S>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StateDef<S> {
+    pub id: S,
+    #[serde(default)]
+    pub on_enter: Vec<ActionDef>,
+    #[serde(default)]
+    pub on_update: Vec<ActionDef>,
+    #[serde(default)]
+    pub on_exit: Vec<ActionDef>,
+    /// seconds spent in the state before any transition can fire
+    #[serde(default)]
+    pub min_time: f32,
+    /// in order of priority
+    #[serde(default)]
+    pub transitions: Vec<TransitionDef<S>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransitionDef<S> {
+    pub to: S,
+    #[serde(default)]
+    pub when: ConditionDef,
+    #[serde(default)]
+    pub timing: Option<TimingDef>,
+}
+
+/// `Timing` with durations that can be named
+#[derive(Debug, Deserialize)]
+pub enum TimingDef {
+    After(Seconds),
+    WithinTicks(u32),
+}
+
+/// Either a number of seconds, or the name of a registered duration
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Seconds {
+    Value(f32),
+    Named(String),
+}
+
+/// Either just the name, or the name along with parameters
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ActionDef {
+    Plain(String),
+    WithParams(String, Params),
+}
+
+#[derive(Debug, Deserialize)]
+pub enum ConditionDef {
+    Always,
+    Is(String),
+    IsWith(String, Params),
+    Not(Box<ConditionDef>),
+    All(Vec<ConditionDef>),
+    Any(Vec<ConditionDef>),
+}
+
+impl Default for ConditionDef {
+    fn default() -> Self {
+        ConditionDef::Always
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Params(HashMap<String, f32>);
+
+impl Params {
+    pub fn get(&self, name: &str) -> Result<f32, MissingParam> {
+        self.0.get(name).copied().ok_or_else(|| MissingParam(name.to_owned()))
+    }
+}
+
+/// Sorted by name, e.g. `accel: 10, target_speed: 64`
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params: Vec<_> = self.0.iter().collect();
+        params.sort_by(|a, b| a.0.cmp(b.0));
+        let params: Vec<_> =
+            params.iter().map(|(name, value)| format!("{}: {}", name, value)).collect();
+        write!(f, "{}", params.join(", "))
+    }
+}
+
+#[derive(Debug)]
+pub struct MissingParam(pub String);
+
+#[derive(Debug)]
+pub enum DefinitionError {
+    Config(ConfigError),
+    UnknownAction(String),
+    UnknownCondition(String),
+    UnknownDuration(String),
+    MissingParam {
+        call: String,
+        param: String,
+    },
+    /// the graph itself is broken, e.g. transition to a state that doesn't exist
+    Build(String),
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefinitionError::Config(err) => write!(f, "{}", err),
+            DefinitionError::UnknownAction(name) => write!(f, "unknown action {}", name),
+            DefinitionError::UnknownCondition(name) => write!(f, "unknown condition {}", name),
+            DefinitionError::UnknownDuration(name) => write!(f, "unknown duration {}", name),
+            DefinitionError::MissingParam { call, param } => {
+                write!(f, "{} is missing parameter {}", call, param)
+            }
+            DefinitionError::Build(errors) => write!(f, "invalid state machine: {}", errors),
+        }
+    }
+}
+
+type ActionFactory = Box<dyn Fn(&Params) -> Result<ContextFn, MissingParam>>;
+type ConditionFactory = Box<dyn Fn(&Params) -> Result<Condition, MissingParam>>;
+
+/// Named actions, conditions and durations a definition can refer to
+#[derive(Default)]
+pub struct Registry {
+    actions: HashMap<String, ActionFactory>,
+    conditions: HashMap<String, ConditionFactory>,
+    /// in seconds
+    durations: HashMap<String, f32>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn action(self, name: &str, action: fn(Entity, &World, &mut Resources)) -> Self {
+        self.action_with(name, move |_params| Ok(Box::new(action) as ContextFn))
+    }
+    pub fn action_with(
+        mut self,
+        name: &str,
+        factory: impl Fn(&Params) -> Result<ContextFn, MissingParam> + 'static,
+    ) -> Self {
+        self.actions.insert(name.to_owned(), Box::new(factory));
+        self
+    }
+    pub fn condition(self, name: &str, condition: fn(Entity, &World, &Resources) -> bool) -> Self {
+        self.condition_with(name, move |_params| Ok(Box::new(condition) as Condition))
+    }
+    pub fn condition_with(
+        mut self,
+        name: &str,
+        factory: impl Fn(&Params) -> Result<Condition, MissingParam> + 'static,
+    ) -> Self {
+        self.conditions.insert(name.to_owned(), Box::new(factory));
+        self
+    }
+    pub fn duration(mut self, name: &str, seconds: f32) -> Self {
+        self.durations.insert(name.to_owned(), seconds);
+        self
+    }
+
+    fn make_timing(&self, def: &TimingDef) -> Result<Timing, DefinitionError> {
+        Ok(match def {
+            TimingDef::After(Seconds::Value(seconds)) => Timing::After(*seconds),
+            TimingDef::After(Seconds::Named(name)) => Timing::After(
+                *self
+                    .durations
+                    .get(name)
+                    .ok_or_else(|| DefinitionError::UnknownDuration(name.clone()))?,
+            ),
+            TimingDef::WithinTicks(ticks) => Timing::WithinTicks(*ticks),
+        })
+    }
+
+    fn make_action(&self, def: &ActionDef) -> Result<ContextFn, DefinitionError> {
+        let empty = Params::default();
+        let (name, params) = match def {
+            ActionDef::Plain(name) => (name, &empty),
+            ActionDef::WithParams(name, params) => (name, params),
+        };
+        let factory =
+            self.actions.get(name).ok_or_else(|| DefinitionError::UnknownAction(name.clone()))?;
+
+        factory(params).map_err(|MissingParam(param)| DefinitionError::MissingParam {
+            call: name.clone(),
+            param,
+        })
+    }
+
+    /// Several actions are merged into one running them in order
+    fn make_actions(&self, defs: &[ActionDef]) -> Result<Option<ContextFn>, DefinitionError> {
+        let mut actions =
+            defs.iter().map(|def| self.make_action(def)).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(match actions.len() {
+            0 => None,
+            1 => actions.pop(),
+            _ => {
+                let merged: ContextFn =
+                    Box::new(move |entity: Entity, world: &World, resources: &mut Resources| {
+                        for action in actions.iter_mut() {
+                            action(entity, world, resources);
+                        }
+                    });
+                Some(merged)
+            }
+        })
+    }
+
+    /// Labeled in a compact form, e.g. `land & !move_directional`
+    fn make_condition(&self, def: &ConditionDef) -> Result<Labeled, DefinitionError> {
+        let named = |name: &String, params: &Params| -> Result<Condition, DefinitionError> {
+            let factory = self
+                .conditions
+                .get(name)
+                .ok_or_else(|| DefinitionError::UnknownCondition(name.clone()))?;
+            factory(params).map_err(|MissingParam(param)| DefinitionError::MissingParam {
+                call: name.clone(),
+                param,
+            })
+        };
+
+        let condition = match def {
+            ConditionDef::Always => Labeled::new("always", always),
+            ConditionDef::Is(name) => Labeled::new(name, named(name, &Params::default())?),
+            ConditionDef::IsWith(name, params) => {
+                Labeled::new(format!("{}({})", name, params), named(name, params)?)
+            }
+            ConditionDef::Not(def) => self.make_condition(def)?.not(),
+            ConditionDef::All(defs) => Labeled::all(self.make_conditions(defs)?),
+            ConditionDef::Any(defs) => Labeled::any(self.make_conditions(defs)?),
+        };
+
+        Ok(condition)
+    }
+
+    fn make_conditions(&self, defs: &[ConditionDef]) -> Result<Vec<Labeled>, DefinitionError> {
+        defs.iter().map(|def| self.make_condition(def)).collect()
+    }
+}
+
+pub fn load_def<S: DeserializeOwned>(path: &str) -> Result<MachineDef<S>, DefinitionError> {
+    config::load(path).map_err(DefinitionError::Config)
+}
+
+/// Definition that didn't come from a file, `name` is used in errors
+pub fn parse_def<S: DeserializeOwned>(
+    name: &str,
+    text: &str,
+) -> Result<MachineDef<S>, DefinitionError> {
+    config::parse(name, text).map_err(DefinitionError::Config)
+}
+
+pub fn build<S: StateId>(
+    def: &MachineDef<S>,
+    registry: &Registry,
+) -> Result<StateMachine<S>, DefinitionError> {
+    let mut builder = StateMachineBuilder::new();
+
+    for state_def in def.states.iter() {
+        let mut state = State::new();
+
+        if let Some(action) = registry.make_actions(&state_def.on_enter)? {
+            state = state.on_enter(action);
+        }
+        if let Some(action) = registry.make_actions(&state_def.on_update)? {
+            state = state.on_update(action);
+        }
+        if let Some(action) = registry.make_actions(&state_def.on_exit)? {
+            state = state.on_exit(action);
+        }
+        state = state.min_time(state_def.min_time);
+
+        for transition in state_def.transitions.iter() {
+            let condition = registry.make_condition(&transition.when)?;
+            state = match &transition.timing {
+                Some(timing) => {
+                    let timing = registry.make_timing(timing)?;
+                    state.add_timed_transition(transition.to, timing, condition)
+                }
+                None => state.add_transition(transition.to, condition),
+            };
+        }
+
+        builder = builder.add_state(state_def.id, state);
+    }
+
+    builder.build(def.initial).map_err(|errors| {
+        let errors: Vec<_> = errors.iter().map(|err| err.to_string()).collect();
+        DefinitionError::Build(errors.join(", "))
+    })
+}
+
+/// Loads the definition and builds a machine from it
+pub fn load<S: StateId + DeserializeOwned>(
+    path: &str,
+    registry: &Registry,
+) -> Result<StateMachine<S>, DefinitionError> {
+    build(&load_def(path)?, registry)
+}
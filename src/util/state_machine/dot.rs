@@ -0,0 +1,173 @@
+/*!
+Export of state machine graphs to Graphviz DOT, e.g. `dot -Tsvg player.dot -o player.svg`.
+
+Transitions are numbered in order of priority, the same index is recorded in `TransitionLog`.
+Conditions restarting a state's time are drawn as dashed loops.
+The point node stands for the machine before its initial state is entered.
+Nested regions are drawn as clusters inside of the state that owns them.
+*/
+use std::collections::HashMap;
+
+use super::{
+    ParallelStateMachine, Region, State, StateId, StateMachine, StateMachineBuilder, Timing,
+};
+
+pub struct DotWriter {
+    out: String,
+    indent: usize,
+}
+
+impl DotWriter {
+    fn new(name: &str) -> Self {
+        let mut dot = Self { out: String::new(), indent: 0 };
+        dot.line(&format!("digraph {} {{", quote(name)));
+        dot.indent += 1;
+        dot.line("node [shape=box, style=rounded];");
+        dot
+    }
+
+    fn finish(mut self) -> String {
+        self.indent -= 1;
+        self.line("}");
+        self.out
+    }
+
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn open_cluster(&mut self, name: &str, label: &str) {
+        self.line(&format!("subgraph {} {{", quote(&format!("cluster_{}", name))));
+        self.indent += 1;
+        self.line(&format!("label={};", quote(label)));
+    }
+
+    fn close_cluster(&mut self) {
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    /// Nodes go first, an edge mentioning a node before it's placed in a cluster would pull
+    /// the node out of it.
+    pub(super) fn write_states<S: StateId>(
+        &mut self,
+        states: &HashMap<S, State<S>>,
+        init_state_id: S,
+        prefix: &str,
+    ) {
+        // sorted so the output doesn't change between runs
+        let mut ids: Vec<S> = states.keys().copied().collect();
+        ids.sort_by_key(|id| format!("{:?}", id));
+
+        let node = |id: &S| quote(&format!("{}{:?}", prefix, id));
+        let entry = quote(&format!("{}entry", prefix));
+
+        self.line(&format!("{} [shape=point];", entry));
+        for id in ids.iter() {
+            let state = &states[id];
+            let mut label = format!("{:?}", id);
+            if state.min_time > 0. {
+                label.push_str(&format!("\nmin {}s", state.min_time));
+            }
+
+            if state.regions.is_empty() {
+                self.line(&format!("{} [label={}];", node(id), quote(&label)));
+                continue;
+            }
+
+            let name = format!("{}{:?}", prefix, id);
+            self.open_cluster(&name, &format!("{:?}", id));
+            self.line(&format!("{} [label={}];", node(id), quote(&label)));
+            for (index, region) in state.regions.iter().enumerate() {
+                if state.regions.len() > 1 {
+                    let region_name = format!("{}/{}", name, index);
+                    self.open_cluster(&region_name, &format!("region {}", index));
+                    region.write_dot(self, &format!("{}/", region_name));
+                    self.close_cluster();
+                } else {
+                    region.write_dot(self, &format!("{}/", name));
+                }
+            }
+            self.close_cluster();
+        }
+
+        self.line(&format!("{} -> {} [label=\"enter\", style=bold];", entry, node(&init_state_id)));
+        for id in ids.iter() {
+            for (_, label) in states[id].restarts.iter() {
+                let label = match label {
+                    Some(label) => format!("restart: {}", label),
+                    None => "restart".to_owned(),
+                };
+                self.line(&format!(
+                    "{} -> {} [label={}, style=dashed];",
+                    node(id),
+                    node(id),
+                    quote(&label)
+                ));
+            }
+            for (index, transition) in states[id].transitions.iter().enumerate() {
+                let mut label = match &transition.label {
+                    Some(label) => format!("{}: {}", index, label),
+                    None => index.to_string(),
+                };
+                match transition.timing {
+                    Some(Timing::After(seconds)) => {
+                        label.push_str(&format!("\nafter {}s", seconds))
+                    }
+                    Some(Timing::WithinTicks(ticks)) => {
+                        label.push_str(&format!("\nwithin {} ticks", ticks))
+                    }
+                    None => (),
+                }
+                self.line(&format!(
+                    "{} -> {} [label={}];",
+                    node(id),
+                    node(&transition.to),
+                    quote(&label)
+                ));
+            }
+        }
+    }
+}
+
+/// Quoted DOT ID, line breaks are kept as `\n` escapes
+fn quote(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+impl<S: StateId> StateMachine<S> {
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = DotWriter::new(name);
+        self.write_dot(&mut dot, "");
+        dot.finish()
+    }
+}
+
+impl<S: StateId> StateMachineBuilder<S> {
+    /// Works for definitions that fail to build too, so broken graphs can be looked at.
+    #[allow(dead_code)]
+    pub fn to_dot(&self, name: &str, init_state_id: S) -> String {
+        let mut dot = DotWriter::new(name);
+        dot.write_states(&self.states, init_state_id, "");
+        dot.finish()
+    }
+}
+
+impl ParallelStateMachine {
+    /// Every region gets a cluster of its own
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = DotWriter::new(name);
+        for (index, region) in self.regions.iter().enumerate() {
+            let region_name = index.to_string();
+            dot.open_cluster(&region_name, &format!("region {}", index));
+            region.write_dot(&mut dot, &format!("{}/", region_name));
+            dot.close_cluster();
+        }
+        dot.finish()
+    }
+}
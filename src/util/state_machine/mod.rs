@@ -0,0 +1,509 @@
+// SILLY NOTES
+// state stores following functions:
+// on enter
+// on update <- could be a container of actions, but it's also elegant to implement this on a higher abstraction level (function aggregating smaller functions)
+// on exit
+
+// open questions: do I need to store any state?
+// do I really want coroutines?
+
+/// States are identified by a user defined type, usually a fieldless enum.
+/// The order of transitions is also their priority, the first met criteria is used.
+/// States can contain nested machines (regions), transitions of the parent are checked before
+/// the ones of its children, so a parent's transition applies to every child state.
+/// A state can't transition to itself, `restart_on` restarts its time in state instead.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+use hecs::{Entity, World};
+
+use crate::game::resources::Resources;
+use crate::util::transition_log::{TransitionLog, TransitionRecord};
+use crate::FRAMETIME;
+
+pub mod definition;
+pub mod dot;
+
+pub type ContextFn = Box<dyn FnMut(Entity, &World, &mut Resources) + Send + Sync>;
+pub type Condition = Box<dyn Fn(Entity, &World, &Resources) -> bool + Send + Sync>;
+
+/// Limits when a transition can fire, based on time spent in the state
+#[derive(Debug, Clone, Copy)]
+pub enum Timing {
+    /// only after spending at least this many seconds in the state
+    After(f32),
+    /// only during this many ticks after entering the state
+    WithinTicks(u32),
+}
+
+impl Timing {
+    fn allows(&self, ticks_in_state: u32) -> bool {
+        match *self {
+            Timing::After(seconds) => ticks_in_state as f32 * FRAMETIME >= seconds,
+            Timing::WithinTicks(ticks) => ticks_in_state <= ticks,
+        }
+    }
+}
+
+struct Transition<S> {
+    to: S,
+    timing: Option<Timing>,
+    condition: Condition,
+    /// readable form of the condition, closures can't tell what they check
+    label: Option<String>,
+}
+
+/// Condition with an optional readable form of it, shown in graph exports and the transition log.
+/// Any condition converts into one without a label, `condition!` derives the label from the
+/// expression itself.
+pub struct Labeled {
+    label: Option<String>,
+    condition: Condition,
+}
+
+impl<F> From<F> for Labeled
+where
+    F: Fn(Entity, &World, &Resources) -> bool + 'static + Send + Sync,
+{
+    fn from(condition: F) -> Self {
+        Self { label: None, condition: Box::new(condition) }
+    }
+}
+
+impl Labeled {
+    pub fn new(
+        label: impl Into<String>,
+        condition: impl Fn(Entity, &World, &Resources) -> bool + 'static + Send + Sync,
+    ) -> Self {
+        Self { label: Some(label.into()), condition: Box::new(condition) }
+    }
+    /// Met when the condition isn't
+    pub fn not(self) -> Self {
+        let label = self.label.map(|label| format!("!{}", nested(label)));
+        Self { label, condition: invert_condition(self.condition) }
+    }
+    #[allow(dead_code)]
+    pub fn and(self, other: impl Into<Labeled>) -> Self {
+        let other = other.into();
+        let label = join_labels(vec![self.label, other.label], " & ");
+        Self { label, condition: and_condition(self.condition, other.condition) }
+    }
+    #[allow(dead_code)]
+    pub fn or(self, other: impl Into<Labeled>) -> Self {
+        let other = other.into();
+        let label = join_labels(vec![self.label, other.label], " | ");
+        Self { label, condition: or_condition(self.condition, other.condition) }
+    }
+    /// Met when every condition is met, checked in order
+    pub fn all(conditions: Vec<Labeled>) -> Self {
+        let (labels, conditions) = conditions.into_iter().map(|c| (c.label, c.condition)).unzip();
+        Self { label: join_labels(labels, " & "), condition: all(conditions) }
+    }
+    /// Met when at least one condition is met, checked in order
+    pub fn any(conditions: Vec<Labeled>) -> Self {
+        let (labels, conditions) = conditions.into_iter().map(|c| (c.label, c.condition)).unzip();
+        Self { label: join_labels(labels, " | "), condition: any(conditions) }
+    }
+}
+
+/// Labels of compound conditions are parenthesized when nested
+fn nested(label: String) -> String {
+    if label.contains(" & ") || label.contains(" | ") {
+        format!("({})", label)
+    } else {
+        label
+    }
+}
+
+/// Compound conditions are only labeled if every part of them is
+fn join_labels(labels: Vec<Option<String>>, separator: &str) -> Option<String> {
+    let labels: Option<Vec<_>> = labels.into_iter().map(|label| label.map(nested)).collect();
+    Some(labels?.join(separator))
+}
+
+/// Labels the condition with its own source, e.g. `condition!(sees_player(SIGHT_RANGE))`
+macro_rules! condition {
+    ($condition:expr) => {
+        $crate::util::state_machine::Labeled::new(stringify!($condition), $condition)
+    };
+}
+pub(crate) use condition;
+
+/// Anything that can identify a state
+pub trait StateId: Copy + Eq + Hash + fmt::Debug + Send + Sync + 'static {}
+
+impl<T: Copy + Eq + Hash + fmt::Debug + Send + Sync + 'static> StateId for T {}
+
+pub struct State<S: StateId> {
+    on_enter: Option<ContextFn>,
+    on_update: Option<ContextFn>,
+    on_exit: Option<ContextFn>,
+    transitions: Vec<Transition<S>>,
+    /// no transition fires before this many seconds in the state
+    min_time: f32,
+    /// nested machines, several of them run in parallel as orthogonal regions
+    regions: Vec<Box<dyn Region>>,
+    /// conditions restarting the time in state, checked before the transitions
+    restarts: Vec<(Condition, Option<String>)>,
+}
+
+impl<S: StateId> State<S> {
+    pub fn new() -> Self {
+        Self {
+            on_enter: None,
+            on_update: None,
+            on_exit: None,
+            transitions: vec![],
+            min_time: 0.,
+            regions: vec![],
+            restarts: vec![],
+        }
+    }
+    pub fn on_enter(
+        self,
+        on_enter: impl FnMut(Entity, &World, &mut Resources) + 'static + Send + Sync,
+    ) -> Self {
+        State { on_enter: Some(Box::new(on_enter)), ..self }
+    }
+    pub fn on_update(
+        self,
+        on_update: impl FnMut(Entity, &World, &mut Resources) + 'static + Send + Sync,
+    ) -> Self {
+        State { on_update: Some(Box::new(on_update)), ..self }
+    }
+    pub fn on_exit(
+        self,
+        on_exit: impl FnMut(Entity, &World, &mut Resources) + 'static + Send + Sync,
+    ) -> Self {
+        State { on_exit: Some(Box::new(on_exit)), ..self }
+    }
+    /// Takes a plain condition, or a `Labeled` one to show its label in graph exports
+    pub fn add_transition(mut self, state_id: S, condition: impl Into<Labeled>) -> Self {
+        let Labeled { label, condition } = condition.into();
+        self.transitions.push(Transition { to: state_id, timing: None, condition, label });
+        self
+    }
+    /// Transition that's only considered when the timing allows it
+    pub fn add_timed_transition(
+        mut self,
+        state_id: S,
+        timing: Timing,
+        condition: impl Into<Labeled>,
+    ) -> Self {
+        let Labeled { label, condition } = condition.into();
+        self.transitions.push(Transition { to: state_id, timing: Some(timing), condition, label });
+        self
+    }
+    /// Restarts the time in state when the condition is met, without leaving the state,
+    /// so its exit and enter don't run
+    pub fn restart_on(mut self, condition: impl Into<Labeled>) -> Self {
+        let Labeled { label, condition } = condition.into();
+        self.restarts.push((condition, label));
+        self
+    }
+    /// Minimum time spent in the state before any of its transitions can fire
+    pub fn min_time(self, seconds: f32) -> Self {
+        State { min_time: seconds, ..self }
+    }
+    /// Nested machine that runs while this state is active.
+    /// It starts from its initial state each time this state is entered.
+    pub fn add_region(mut self, region: impl Region + 'static) -> Self {
+        self.regions.push(Box::new(region));
+        self
+    }
+}
+
+/// Condition that's always met, for transitions that only depend on timing
+pub fn always(_entity: Entity, _world: &World, _resources: &Resources) -> bool {
+    true
+}
+
+// Could conditions be function objects for clearer API?
+pub fn invert_condition(
+    condition: impl Fn(Entity, &World, &Resources) -> bool + 'static + Send + Sync,
+) -> Condition {
+    Box::new(move |entity: Entity, world: &World, resources: &Resources| {
+        !condition(entity, world, resources)
+    })
+}
+
+pub fn and_condition(
+    condition1: impl Fn(Entity, &World, &Resources) -> bool + 'static + Send + Sync,
+    condition2: impl Fn(Entity, &World, &Resources) -> bool + 'static + Send + Sync,
+) -> Condition {
+    Box::new(move |entity: Entity, world: &World, resources: &Resources| {
+        condition1(entity, world, resources) && condition2(entity, world, resources)
+    })
+}
+
+pub fn or_condition(
+    condition1: impl Fn(Entity, &World, &Resources) -> bool + 'static + Send + Sync,
+    condition2: impl Fn(Entity, &World, &Resources) -> bool + 'static + Send + Sync,
+) -> Condition {
+    Box::new(move |entity: Entity, world: &World, resources: &Resources| {
+        condition1(entity, world, resources) || condition2(entity, world, resources)
+    })
+}
+
+/// Met when every condition is met, checked in order
+pub fn all(conditions: Vec<Condition>) -> Condition {
+    Box::new(move |entity: Entity, world: &World, resources: &Resources| {
+        conditions.iter().all(|condition| condition(entity, world, resources))
+    })
+}
+
+/// Met when at least one condition is met, checked in order
+pub fn any(conditions: Vec<Condition>) -> Condition {
+    Box::new(move |entity: Entity, world: &World, resources: &Resources| {
+        conditions.iter().any(|condition| condition(entity, world, resources))
+    })
+}
+
+/// Machine that can be nested in a state or run in parallel, regardless of its state ID type
+pub trait Region: Send + Sync {
+    fn update(&mut self, entity: Entity, world: &World, resources: &mut Resources);
+    /// Exits the active state, children first, so the next update starts from the initial state.
+    fn exit(&mut self, entity: Entity, world: &World, resources: &mut Resources);
+    /// Active state along with the active states of its regions, for debugging
+    fn describe(&self) -> String;
+    /// Writes the graph as DOT statements, node names are prefixed to keep them unique
+    fn write_dot(&self, dot: &mut dot::DotWriter, prefix: &str);
+}
+
+fn describe_regions(regions: &[Box<dyn Region>]) -> String {
+    regions.iter().map(|region| region.describe()).collect::<Vec<_>>().join(" | ")
+}
+
+// FSM stores hashmap of states linked to enums
+pub struct StateMachine<S: StateId> {
+    states: HashMap<S, State<S>>,
+    init_state: S,
+    /// `None` until the initial state is entered
+    active_state: Option<S>,
+    /// updates spent in the active state, counting the one it was entered in
+    ticks_in_state: u32,
+}
+
+impl<S: StateId> StateMachine<S> {
+    #[allow(dead_code)]
+    pub fn active_state(&self) -> Option<S> {
+        self.active_state
+    }
+    /// Seconds spent in the active state
+    #[allow(dead_code)]
+    pub fn time_in_state(&self) -> f32 {
+        self.ticks_in_state as f32 * FRAMETIME
+    }
+
+    fn enter(&mut self, state_id: S, entity: Entity, world: &World, resources: &mut Resources) {
+        self.active_state = Some(state_id);
+        self.ticks_in_state = 0;
+        self.states
+            .get_mut(&state_id)
+            .unwrap()
+            .on_enter
+            .iter_mut()
+            .for_each(|fun| fun(entity, world, resources));
+    }
+}
+
+impl<S: StateId> Region for StateMachine<S> {
+    fn update(&mut self, entity: Entity, world: &World, resources: &mut Resources) {
+        match self.active_state {
+            None => self.enter(self.init_state, entity, world, resources),
+            Some(active_state) => {
+                let state = self.states.get(&active_state).unwrap();
+                if state.restarts.iter().any(|(condition, _)| condition(entity, world, resources)) {
+                    self.ticks_in_state = 0;
+                }
+                let ticks_in_state = self.ticks_in_state;
+                let settled = Timing::After(state.min_time).allows(ticks_in_state);
+                // check if transition occurs
+                let next_state = state
+                    .transitions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, transition)| {
+                        settled
+                            && transition
+                                .timing
+                                .map_or(true, |timing| timing.allows(ticks_in_state))
+                    })
+                    .find(|(_, transition)| (transition.condition)(entity, world, resources))
+                    .map(|(index, transition)| (index, transition.to, transition.label.clone()));
+                // transition if it does
+                if let Some((index, next_state, label)) = next_state {
+                    if let Ok(mut log) = world.get_mut::<TransitionLog>(entity) {
+                        log.record(TransitionRecord {
+                            tick: resources.tick,
+                            from: format!("{:?}", active_state),
+                            to: format!("{:?}", next_state),
+                            transition: index,
+                            label,
+                        });
+                    }
+                    self.exit(entity, world, resources);
+                    self.enter(next_state, entity, world, resources);
+                }
+            }
+        }
+
+        // perform update of the current state, then of its children
+        self.ticks_in_state += 1;
+        let state = self.states.get_mut(&self.active_state.unwrap()).unwrap();
+        state.on_update.iter_mut().for_each(|fun| fun(entity, world, resources));
+        state.regions.iter_mut().for_each(|region| region.update(entity, world, resources));
+    }
+
+    fn exit(&mut self, entity: Entity, world: &World, resources: &mut Resources) {
+        if let Some(active_state) = self.active_state.take() {
+            let state = self.states.get_mut(&active_state).unwrap();
+            state.regions.iter_mut().for_each(|region| region.exit(entity, world, resources));
+            state.on_exit.iter_mut().for_each(|fun| fun(entity, world, resources));
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.active_state {
+            Some(active_state) => {
+                let regions = &self.states[&active_state].regions;
+                match regions.len() {
+                    0 => format!("{:?}", active_state),
+                    1 => format!("{:?} > {}", active_state, regions[0].describe()),
+                    _ => format!("{:?} > ({})", active_state, describe_regions(regions)),
+                }
+            }
+            None => "-".to_owned(),
+        }
+    }
+
+    fn write_dot(&self, dot: &mut dot::DotWriter, prefix: &str) {
+        dot.write_states(&self.states, self.init_state, prefix);
+    }
+}
+
+/// Independent machines updated one after another, e.g. locomotion and action
+pub struct ParallelStateMachine {
+    regions: Vec<Box<dyn Region>>,
+}
+
+impl ParallelStateMachine {
+    pub fn new() -> Self {
+        Self { regions: vec![] }
+    }
+    pub fn add_region(mut self, region: impl Region + 'static) -> Self {
+        self.regions.push(Box::new(region));
+        self
+    }
+    pub fn update(&mut self, entity: Entity, world: &World, resources: &mut Resources) {
+        for region in self.regions.iter_mut() {
+            region.update(entity, world, resources);
+        }
+    }
+    pub fn exit(&mut self, entity: Entity, world: &World, resources: &mut Resources) {
+        for region in self.regions.iter_mut() {
+            region.exit(entity, world, resources);
+        }
+    }
+    pub fn describe(&self) -> String {
+        describe_regions(&self.regions)
+    }
+}
+
+#[derive(Debug)]
+pub enum BuildError<S: StateId> {
+    /// the same ID was used for two states
+    DuplicateState(S),
+    /// the initial state isn't defined
+    MissingInitialState(S),
+    /// transition leads to a state that isn't defined
+    MissingState { from: S, to: S },
+    /// state can't be left for itself, its exit and enter would never run
+    SelfTransition(S),
+    /// no chain of transitions leads from the initial state to this one
+    Unreachable(S),
+}
+
+impl<S: StateId> fmt::Display for BuildError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::DuplicateState(id) => write!(f, "state {:?} defined twice", id),
+            BuildError::MissingInitialState(id) => {
+                write!(f, "initial state {:?} is not defined", id)
+            }
+            BuildError::MissingState { from, to } => {
+                write!(f, "transition from {:?} to undefined state {:?}", from, to)
+            }
+            BuildError::SelfTransition(id) => write!(f, "state {:?} transitions to itself", id),
+            BuildError::Unreachable(id) => write!(f, "state {:?} is unreachable", id),
+        }
+    }
+}
+
+pub struct StateMachineBuilder<S: StateId> {
+    states: HashMap<S, State<S>>,
+    duplicates: Vec<S>,
+}
+
+impl<S: StateId> StateMachineBuilder<S> {
+    pub fn new() -> Self {
+        Self { states: HashMap::new(), duplicates: vec![] }
+    }
+
+    pub fn add_state(mut self, id: S, state: State<S>) -> Self {
+        if self.states.insert(id, state).is_some() {
+            self.duplicates.push(id);
+        }
+        self
+    }
+
+    /// Validates the definition, every problem found is reported.
+    pub fn build(self, init_state_id: S) -> Result<StateMachine<S>, Vec<BuildError<S>>> {
+        let mut errors: Vec<_> =
+            self.duplicates.into_iter().map(BuildError::DuplicateState).collect();
+
+        if !self.states.contains_key(&init_state_id) {
+            errors.push(BuildError::MissingInitialState(init_state_id));
+        }
+
+        for (&from, state) in self.states.iter() {
+            for transition in state.transitions.iter() {
+                let to = transition.to;
+                if from == to {
+                    errors.push(BuildError::SelfTransition(from));
+                } else if !self.states.contains_key(&to) {
+                    errors.push(BuildError::MissingState { from, to });
+                }
+            }
+        }
+
+        // walk the transitions starting from the initial state
+        let mut reached = HashSet::new();
+        let mut to_visit = vec![init_state_id];
+        while let Some(id) = to_visit.pop() {
+            if let Some(state) = self.states.get(&id) {
+                if reached.insert(id) {
+                    to_visit.extend(state.transitions.iter().map(|transition| transition.to));
+                }
+            }
+        }
+        errors.extend(
+            self.states
+                .keys()
+                .filter(|id| !reached.contains(id))
+                .map(|&id| BuildError::Unreachable(id)),
+        );
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(StateMachine {
+            states: self.states,
+            init_state: init_state_id,
+            active_state: None,
+            ticks_in_state: 0,
+        })
+    }
+}
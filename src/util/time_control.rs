@@ -0,0 +1,61 @@
+/*!
+Debugging controls over the speed of the simulation.
+F7 pauses, F8 advances a single update and pauses, F9 and F10 slow down and speed up.
+*/
+use macroquad::input::{is_key_pressed, KeyCode};
+
+/// Speeds F9 and F10 step through
+pub const SPEEDS: [f64; 4] = [0.25, 0.5, 1., 2.];
+
+pub struct TimeControl {
+    /// multiplier of the simulation speed
+    pub scale: f64,
+    pub paused: bool,
+}
+
+impl TimeControl {
+    pub fn new() -> Self {
+        Self { scale: 1., paused: false }
+    }
+
+    /// Returns true if a single update was requested
+    pub fn handle_keys(&mut self) -> bool {
+        if is_key_pressed(KeyCode::F7) {
+            self.paused = !self.paused;
+        }
+        if is_key_pressed(KeyCode::F9) {
+            self.scale = SPEEDS
+                .iter()
+                .rev()
+                .copied()
+                .find(|&speed| speed < self.scale)
+                .unwrap_or(self.scale);
+        }
+        if is_key_pressed(KeyCode::F10) {
+            self.scale =
+                SPEEDS.iter().copied().find(|&speed| speed > self.scale).unwrap_or(self.scale);
+        }
+
+        let step = is_key_pressed(KeyCode::F8);
+        if step {
+            self.paused = true;
+        }
+        step
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        if self.paused {
+            0.
+        } else {
+            self.scale
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        if self.paused {
+            "paused".to_owned()
+        } else {
+            format!("x{}", self.scale)
+        }
+    }
+}
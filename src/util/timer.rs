@@ -1,9 +1,103 @@
 use std::ops::{Div, Sub};
+use std::str::FromStr;
 use std::time::{Duration, Instant};
 
 use ringbuffer::{ConstGenericRingBuffer, RingBufferExt, RingBufferWrite};
+use serde::{Deserialize, Serialize};
 
 const BUFFER_SIZE: usize = 8;
+/// used by `capped` without a number, and in place of an invalid cap
+const DEFAULT_MAX_UPDATES: u32 = 4;
+
+pub const TIMING_CONFIG_PATH: &str = "config/timing.ron";
+
+/// How elapsed time is turned into fixed updates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimestepStrategy {
+    /// every bit of measured time is simulated, exactly as measured
+    Strict,
+    /// measured time is smoothed so jittery frame pacing doesn't cause doubled or skipped updates
+    Fuzzy { snapping: bool, averaging: bool },
+    /// like `Strict`, but the backlog left after this many updates in a frame is dropped,
+    /// so a slow update can't keep making the next frame slower
+    CappedCatchUp { max_updates: u32 },
+}
+
+impl Default for TimestepStrategy {
+    fn default() -> Self {
+        TimestepStrategy::Fuzzy { snapping: false, averaging: true }
+    }
+}
+
+/// Accepts `strict`, `fuzzy`, `capped` and `capped:<max updates>`
+impl FromStr for TimestepStrategy {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.split_once(':') {
+            None if text == "strict" => Ok(TimestepStrategy::Strict),
+            None if text == "fuzzy" => Ok(TimestepStrategy::default()),
+            None if text == "capped" => {
+                Ok(TimestepStrategy::CappedCatchUp { max_updates: DEFAULT_MAX_UPDATES })
+            }
+            Some(("capped", max_updates)) => match max_updates.parse() {
+                Ok(0) | Err(_) => Err(format!("invalid update cap {}", max_updates)),
+                Ok(max_updates) => Ok(TimestepStrategy::CappedCatchUp { max_updates }),
+            },
+            _ => Err(format!("unknown timestep strategy {}", text)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimingConfig {
+    pub strategy: TimestepStrategy,
+    /// frames taking longer than this many seconds are treated as a stall and not caught up on
+    pub stall_threshold: f32,
+}
+
+impl Default for TimingConfig {
+    fn default() -> Self {
+        Self { strategy: TimestepStrategy::default(), stall_threshold: 0.25 }
+    }
+}
+
+impl TimingConfig {
+    /// Config file first, `--timestep <strategy>` on the command line overrides it.
+    /// Invalid values are replaced with the defaults
+    pub fn load() -> Self {
+        let mut config: Self = super::config::load_or_default(TIMING_CONFIG_PATH);
+
+        if !(config.stall_threshold.is_finite() && config.stall_threshold > 0.) {
+            let default = Self::default().stall_threshold;
+            log::warn!(
+                "Invalid stall threshold {} in {}, using {}",
+                config.stall_threshold,
+                TIMING_CONFIG_PATH,
+                default
+            );
+            config.stall_threshold = default;
+        }
+        if let TimestepStrategy::CappedCatchUp { max_updates: 0 } = config.strategy {
+            log::warn!(
+                "Update cap can't be 0 in {}, using {}",
+                TIMING_CONFIG_PATH,
+                DEFAULT_MAX_UPDATES
+            );
+            config.strategy = TimestepStrategy::CappedCatchUp { max_updates: DEFAULT_MAX_UPDATES };
+        }
+
+        let mut args = std::env::args().skip_while(|arg| arg != "--timestep").skip(1);
+        if let Some(arg) = args.next() {
+            match arg.parse() {
+                Ok(strategy) => config.strategy = strategy,
+                Err(err) => log::warn!("Ignoring --timestep, {}", err),
+            }
+        }
+        config
+    }
+}
 
 pub struct FrameTimer {
     desired_frametime: Duration,
@@ -12,45 +106,48 @@ pub struct FrameTimer {
     unused_time: Duration,
 
     averager: ConstGenericRingBuffer<Duration, BUFFER_SIZE>,
-    pub time_snapping: bool,
-    pub time_averaging: bool,
+    strategy: TimestepStrategy,
+    stall_threshold: Duration,
+    /// multiplier of the elapsed time, 0 stops the updates
+    time_scale: f64,
     elapsed: Duration,
+    /// updates done since the last `process_elapsed`
+    updates: u32,
 }
 
 impl FrameTimer {
-    pub fn with_fps(fps: f64) -> FrameTimer {
+    pub fn with_fps(fps: f64, config: &TimingConfig) -> FrameTimer {
         let desired_frametime = Duration::from_secs_f64(1.0 / fps);
 
-        let mut averager = ConstGenericRingBuffer::<Duration, BUFFER_SIZE>::new();
-        for _ in 0..BUFFER_SIZE {
-            averager.push(desired_frametime);
-        }
-
-        Self {
+        let mut timer = Self {
             desired_frametime,
             last_update: Instant::now(),
             unused_time: Duration::ZERO,
-            time_snapping: true,
-            averager,
-            time_averaging: true,
+            averager: ConstGenericRingBuffer::new(),
+            strategy: config.strategy,
+            stall_threshold: Duration::from_secs_f32(config.stall_threshold),
+            time_scale: 1.,
             elapsed: Duration::ZERO,
-        }
+            updates: 0,
+        };
+        timer.reset_averager();
+        timer
     }
-    // http://lspiroengine.com/?p=378
-    // pub fn tick(&mut self, current_time: &Instant) -> bool {
-    //     if current_time.duration_since(self.last_update) > self.frametime {
-    //         self.last_update += self.frametime;
-    //         // println!("Leftover: {:#?}", current_time.duration_since(self.last_update));
-    //         true
-    //     } else {
-    //         false
-    //     }
-    // }
 
     // https://github.com/TylerGlaiel/FrameTimingControl/blob/master/frame_timer.cpp
-    pub fn fuzzy_tick(&mut self) -> bool {
+    pub fn tick(&mut self) -> bool {
+        if let TimestepStrategy::CappedCatchUp { max_updates } = self.strategy {
+            if self.updates >= max_updates {
+                // keep the part that's not a whole update, so pacing stays even
+                let leftover = self.unused_time.as_nanos() % self.desired_frametime.as_nanos();
+                self.unused_time = Duration::from_nanos(leftover as u64);
+                return false;
+            }
+        }
+
         if self.unused_time >= self.desired_frametime {
             self.unused_time -= self.desired_frametime;
+            self.updates += 1;
             true
         } else {
             false
@@ -67,22 +164,32 @@ impl FrameTimer {
 
         // update to the last measured timepoint
         self.last_update += self.elapsed;
+        self.updates = 0;
 
-        // vsync time snapping
-        if self.time_snapping {
-            let timediff = timediff_abs(&delta_frametime, &self.desired_frametime);
+        // window drags, breakpoints and loading would be caught up on all at once otherwise
+        if delta_frametime > self.stall_threshold {
+            log::info!("Stalled for {:.2}s, resyncing", delta_frametime.as_secs_f32());
+            self.resync();
+            return;
+        }
 
-            if timediff < Duration::from_secs_f64(0.0002) {
-                delta_frametime = Duration::from_secs_f64(1.0 / 60.)
+        if let TimestepStrategy::Fuzzy { snapping, averaging } = self.strategy {
+            // vsync time snapping
+            if snapping {
+                let timediff = timediff_abs(&delta_frametime, &self.desired_frametime);
+
+                if timediff < Duration::from_secs_f64(0.0002) {
+                    delta_frametime = Duration::from_secs_f64(1.0 / 60.)
+                }
             }
-        }
 
-        // average delta time according to previous values (smoothen)
-        if self.time_averaging {
-            delta_frametime = self.time_averaging(&delta_frametime);
+            // average delta time according to previous values (smoothen)
+            if averaging {
+                delta_frametime = self.time_averaging(&delta_frametime);
+            }
         }
 
-        self.unused_time += delta_frametime;
+        self.unused_time += delta_frametime.mul_f64(self.time_scale);
     }
 
     fn time_averaging(&mut self, delta_frametime: &Duration) -> Duration {
@@ -90,8 +197,20 @@ impl FrameTimer {
         self.averager.iter().sum::<Duration>().div(BUFFER_SIZE as u32)
     }
 
+    fn reset_averager(&mut self) {
+        for _ in 0..BUFFER_SIZE {
+            self.averager.push(self.desired_frametime);
+        }
+    }
+
+    #[cfg(feature = "devtools")]
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale;
+    }
+
     pub fn resync(&mut self) {
         self.unused_time = Duration::ZERO;
+        self.reset_averager();
     }
 }
 
@@ -102,37 +221,3 @@ fn timediff_abs(a: &Duration, b: &Duration) -> Duration {
         a.sub(*b)
     }
 }
-
-pub struct UpdateHistogram {
-    histogram: fxhash::FxHashMap<usize, usize>,
-    counter: usize,
-
-    print_count: usize,
-}
-
-impl UpdateHistogram {
-    pub fn new() -> Self {
-        Self { print_count: 0, counter: 0, histogram: fxhash::FxHashMap::<usize, usize>::default() }
-    }
-    pub fn register_update(&mut self) {
-        self.counter += 1;
-    }
-
-    pub fn tick(&mut self) {
-        let val = self.histogram.entry(self.counter).or_default();
-        *val += 1;
-
-        self.counter = 0;
-
-        self.print_count += 1;
-        let numloops = 600;
-
-        if self.print_count >= numloops {
-            println!("Last period:");
-            for (key, val) in self.histogram.drain() {
-                println!("{}: {}", key, val);
-            }
-            self.print_count = 0;
-        }
-    }
-}
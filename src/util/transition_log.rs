@@ -0,0 +1,37 @@
+use ringbuffer::{ConstGenericRingBuffer, RingBuffer, RingBufferExt, RingBufferWrite};
+
+const LOG_SIZE: usize = 16;
+
+/// A single state change of a `StateMachine`
+#[derive(Debug, Clone)]
+pub struct TransitionRecord {
+    /// update in which the transition happened
+    pub tick: u64,
+    pub from: String,
+    pub to: String,
+    /// index of the transition that fired, in order they were added to the state
+    pub transition: usize,
+    /// label of its condition, if it has one
+    pub label: Option<String>,
+}
+
+/// Component, state machines of entities with it record their transitions here.
+pub struct TransitionLog {
+    records: ConstGenericRingBuffer<TransitionRecord, LOG_SIZE>,
+    /// description of the active states, nested regions included
+    pub active: String,
+}
+
+impl TransitionLog {
+    pub fn new() -> Self {
+        Self { records: ConstGenericRingBuffer::new(), active: String::new() }
+    }
+    pub fn record(&mut self, record: TransitionRecord) {
+        self.records.push(record);
+    }
+    /// Recorded transitions, newest first
+    pub fn recent(&self) -> impl Iterator<Item = &TransitionRecord> {
+        // negative indices count from the newest element
+        (1..=self.records.len() as isize).filter_map(move |index| self.records.get(-index))
+    }
+}